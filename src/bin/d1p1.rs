@@ -3,7 +3,10 @@ use std::io::prelude::*;
 
 use clap::Parser;
 
+use color_eyre::eyre::{Report, Result};
+
 use aoc2022::inputs::InputCLI;
+use aoc2022::iterators::AocItertools;
 
 #[derive(Parser)]
 struct Cli {
@@ -15,18 +18,9 @@ fn main() -> color_eyre::Result<()> {
     color_eyre::install()?;
     let cli = Cli::parse();
 
-    let mut calories = Vec::new();
-    let mut thiscals = 0;
-
-    for line in cli.input.get_input()?.lines().flatten() {
-        let line = line.trim();
-        if line.is_empty() {
-            calories.push(thiscals);
-            thiscals = 0;
-        } else {
-            thiscals += line.parse::<u32>().unwrap();
-        }
-    }
+    let mut calories = cli.input.get_input()?.lines().try_paragraphs()
+        .map_and_then(|elf| elf.iter().map(|line| line.trim().parse::<u32>().map_err(Report::from)).sum())
+        .collect::<Result<Vec<u32>>>()?;
 
     calories.sort();
 
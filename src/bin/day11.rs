@@ -1,10 +1,17 @@
 use aoc2022::prelude::*;
+use aoc2022::cycle::CycleDetector;
 use itertools::Itertools;
 
 #[derive(Parser)]
 struct Cli {
     #[command(flatten)]
-    input: InputCLI<11>
+    input: InputCLI<11>,
+    /// Number of rounds to play in part 2
+    #[arg(long, default_value_t = 10_000)]
+    rounds: u64,
+    /// Print every inspection and throw
+    #[arg(long)]
+    trace: bool,
 }
 
 trait SliceExt {
@@ -97,50 +104,150 @@ impl<'a, T> Iterator for IterAroundMutSlice<'a, T> where Self: 'a {
 
 #[derive(Clone)]
 struct Item {
-    worry: i64
+    worry: i128
 }
 
 #[derive(Clone)]
 enum Expression {
     OldValue,
-    Constant(i64),
+    Constant(i128),
     Add(Box<Expression>, Box<Expression>),
-    Mul(Box<Expression>, Box<Expression>)
+    Sub(Box<Expression>, Box<Expression>),
+    Mul(Box<Expression>, Box<Expression>),
+    Pow(Box<Expression>, Box<Expression>)
 }
 
-impl From<i64> for Expression {
-    fn from(c: i64) -> Self {
+impl From<i128> for Expression {
+    fn from(c: i128) -> Self {
         Self::Constant(c)
     }
 }
 
-impl From<i64> for Box<Expression> {
-    fn from(c: i64) -> Self {
+impl From<i128> for Box<Expression> {
+    fn from(c: i128) -> Self {
         Box::new(Expression::Constant(c))
     }
 }
 
+impl std::fmt::Display for Expression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::OldValue => write!(f, "old"),
+            Self::Constant(c) => write!(f, "{}", c),
+            Self::Add(l, r) => write!(f, "({} + {})", l, r),
+            Self::Sub(l, r) => write!(f, "({} - {})", l, r),
+            Self::Mul(l, r) => write!(f, "({} * {})", l, r),
+            Self::Pow(l, r) => write!(f, "({} ^ {})", l, r),
+        }
+    }
+}
+
+/// `base ^ exp` reduced by `modulus`, or None if the intermediate products overflow
+fn checked_mod_pow(base: i128, mut exp: i128, modulus: i128) -> Option<i128> {
+    let mut result = 1i128.rem_euclid(modulus);
+    let mut base = base.rem_euclid(modulus);
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = result.checked_mul(base)?.rem_euclid(modulus);
+        }
+        base = base.checked_mul(base)?.rem_euclid(modulus);
+        exp >>= 1;
+    }
+    Some(result)
+}
+
+impl Expression {
+    fn depends_on_old(&self) -> bool {
+        match self {
+            Self::OldValue => true,
+            Self::Constant(_) => false,
+            Self::Add(l, r) | Self::Sub(l, r) | Self::Mul(l, r) | Self::Pow(l, r) =>
+                l.depends_on_old() || r.depends_on_old()
+        }
+    }
+
+    /// Exponents are the one place a worry value can't be reduced, so they must not use `old`
+    fn has_constant_exponents(&self) -> bool {
+        match self {
+            Self::OldValue | Self::Constant(_) => true,
+            Self::Pow(l, r) => l.has_constant_exponents() && !r.depends_on_old(),
+            Self::Add(l, r) | Self::Sub(l, r) | Self::Mul(l, r) =>
+                l.has_constant_exponents() && r.has_constant_exponents()
+        }
+    }
+
+    /// Evaluate without reducing anything, as exponents need
+    fn eval_exact(&self, old: i128) -> Result<i128> {
+        let overflow = || eyre!("Worry overflowed evaluating {} with old = {}", self, old);
+        match self {
+            Self::OldValue => Ok(old),
+            Self::Constant(c) => Ok(*c),
+            Self::Add(l, r) => l.eval_exact(old)?.checked_add(r.eval_exact(old)?).ok_or_else(overflow),
+            Self::Sub(l, r) => l.eval_exact(old)?.checked_sub(r.eval_exact(old)?).ok_or_else(overflow),
+            Self::Mul(l, r) => l.eval_exact(old)?.checked_mul(r.eval_exact(old)?).ok_or_else(overflow),
+            Self::Pow(l, r) => {
+                let exp = r.eval_exact(old)?;
+                if exp < 0 {
+                    bail!("Negative exponent {} in {}", exp, self);
+                }
+                let exp = u32::try_from(exp).map_err(|_| overflow())?;
+                l.eval_exact(old)?.checked_pow(exp).ok_or_else(overflow)
+            }
+        }
+    }
+
+    /// Evaluate with every intermediate value reduced by `modulus`. Since only ring operations
+    /// are involved this gives the true result modulo `modulus`
+    pub fn eval(&self, old: i128, modulus: i128) -> Result<i128> {
+        let binop = |l: &Expression, r: &Expression, op: fn(i128, i128) -> Option<i128>| -> Result<i128> {
+            op(l.eval(old, modulus)?, r.eval(old, modulus)?)
+                .ok_or_else(|| eyre!("Worry overflowed evaluating {} with old = {}", self, old))
+        };
+        let value = match self {
+            Self::OldValue => old,
+            Self::Constant(c) => *c,
+            Self::Add(l, r) => binop(l, r, i128::checked_add)?,
+            Self::Sub(l, r) => binop(l, r, i128::checked_sub)?,
+            Self::Mul(l, r) => binop(l, r, i128::checked_mul)?,
+            Self::Pow(l, r) => {
+                let exp = r.eval_exact(old)?;
+                if exp < 0 {
+                    bail!("Negative exponent {} in {}", exp, self);
+                }
+                checked_mod_pow(l.eval(old, modulus)?, exp, modulus)
+                    .ok_or_else(|| eyre!("Worry overflowed evaluating {} with old = {}", self, old))?
+            }
+        };
+        Ok(value.rem_euclid(modulus))
+    }
+}
+
 #[derive(Clone)]
 enum Test {
-    DivisibleBy(i64)
+    DivisibleBy(i128)
 }
 
-impl Item {
-    pub fn new(worry: i64) -> Self {
-        Self { worry }
+impl Test {
+    /// Worry values can be reduced by any multiple of this without changing the test result
+    fn period(&self) -> i128 {
+        match self {
+            Test::DivisibleBy(d) => *d
+        }
     }
+}
 
-    pub fn eval_expression(&self, expr: &Expression) -> i64 {
-        match expr {
-            Expression::OldValue => self.worry,
-            Expression::Constant(x) => *x,
-            Expression::Add(l, r) => self.eval_expression(l) + self.eval_expression(r),
-            Expression::Mul(l, r) => self.eval_expression(l) * self.eval_expression(r),
-        }
+fn gcd(a: i128, b: i128) -> i128 {
+    if b == 0 { a.abs() } else { gcd(b, a % b) }
+}
+
+impl Item {
+    pub fn new(worry: i128) -> Self {
+        Self { worry }
     }
 
-    pub fn update_worry(&mut self, expr: &Expression) {
-        self.worry = self.eval_expression(expr);
+    pub fn update_worry(&mut self, expr: &Expression, modulus: i128) -> Result<()> {
+        self.worry = expr.eval(self.worry, modulus)?;
+        Ok(())
     }
 
     pub fn eval_test(&self, test: &Test) -> bool {
@@ -168,130 +275,343 @@ struct Throw {
 
 impl Monkey {
 
-    fn new<const N:usize>(id: usize, held_items: [i64; N], inspect_operation: Expression, divby: i64, true_monkey: usize, false_monkey: usize) -> Self {
-        let held_items = held_items.iter().map(|w| Item::new(*w)).collect();
-        let test = Test::DivisibleBy(divby);
-        Self { id, held_items, inspect_operation, test, true_monkey, false_monkey, inspection_count: 0 }
-    }
-
-    fn take_a_turn(&mut self) -> Vec<Throw> {
-        println!("Monkey {}'s turn", self.id);
-        self.held_items.drain(0..).enumerate()
-            .inspect(|(i,_)| println!("  Inspecting item {}", i))
-            .map(|(_,item)| item)
-            .inspect(|item| println!("      Worry was {}", item.worry))
-            .update(|item| item.update_worry(&self.inspect_operation))
-            .inspect(|item| println!("      Worry is now {}", item.worry))
-            .inspect(|_| self.inspection_count += 1)
-            .update(|item| item.worry /= 3)
-            .inspect(|item| println!("      Phew, worry reduced to {}", item.worry))
-            .map(|item| {
-                let test_result = item.eval_test(&self.test);
-                let to_monkey = if test_result { self.true_monkey } else { self.false_monkey };
-                println!("    Test was {}, throwing to {}", test_result, to_monkey);
-                Throw { item, to_monkey }
-            })
-            .collect()
-    }
-
-    fn take_a_turn_part2(&mut self) -> Vec<Throw> {
-        println!("Monkey {}'s turn", self.id);
+    fn take_a_turn(&mut self, modulus: i128, relief: Relief, trace: bool) -> Result<Vec<Throw>> {
+        if trace { println!("Monkey {}'s turn", self.id); }
         self.held_items.drain(0..).enumerate()
-            .inspect(|(i,_)| println!("  Inspecting item {}", i))
+            .inspect(|(i,_)| if trace { println!("  Inspecting item {}", i) })
             .map(|(_,item)| item)
-            .inspect(|item| println!("      Worry was {}", item.worry))
-            .update(|item| item.update_worry(&self.inspect_operation))
-            .inspect(|item| println!("      Worry is now {}", item.worry))
-            .inspect(|_| self.inspection_count += 1)
-            .map(|item| {
+            .inspect(|item| if trace { println!("      Worry was {}", item.worry) })
+            .map(|mut item| item.update_worry(&self.inspect_operation, modulus).map(|_| item))
+            .map_ok(|item| {
+                if trace { println!("      Worry is now {}", item.worry); }
+                self.inspection_count += 1;
+                let item = match relief {
+                    Relief::DivideBy(d) => {
+                        let item = Item::new(item.worry.div_euclid(d));
+                        if trace { println!("      Phew, worry reduced to {}", item.worry); }
+                        item
+                    }
+                    Relief::None => item
+                };
                 let test_result = item.eval_test(&self.test);
                 let to_monkey = if test_result { self.true_monkey } else { self.false_monkey };
-                println!("    Test was {}, throwing to {}", test_result, to_monkey);
+                if trace { println!("    Test was {}, throwing to {}", test_result, to_monkey); }
                 Throw { item, to_monkey }
             })
             .collect()
     }
 
-    fn catch(&mut self, throw: Throw) {
+    fn catch(&mut self, throw: Throw, trace: bool) {
         assert!(self.id == throw.to_monkey, "id: {}, thrown to: {}", self.id, throw.to_monkey);
-        println!("  Monkey {} catches item with worry {}", self.id, throw.item.worry);
+        if trace { println!("  Monkey {} catches item with worry {}", self.id, throw.item.worry); }
         self.held_items.push(throw.item)
     }
 }
 
+/// What happens to an item's worry level after a monkey inspects it
+#[derive(Clone, Copy, Debug)]
+enum Relief {
+    DivideBy(i128),
+    None
+}
+
+impl Relief {
+    fn divisor(&self) -> i128 {
+        match self {
+            Relief::DivideBy(d) => *d,
+            Relief::None => 1
+        }
+    }
+}
+
 struct Keepaway {
     monkeys: Vec<Monkey>,
-    reduction_constant: i64,
+    relief: Relief,
+    /// Worry values are reduced modulo this. It is the least common multiple of the periods of
+    /// all the monkeys' tests, times the relief divisor so that the division stays exact
+    modulus: i128,
+    trace: bool,
 }
 
 impl Keepaway {
 
-    fn new(monkeys: Vec<Monkey>) -> Self {
-        let reduction_constant = monkeys.iter().map(|m| match m.test { Test::DivisibleBy(d) => d }).product();
-        Self { monkeys, reduction_constant }
+    fn new(monkeys: Vec<Monkey>, relief: Relief) -> Result<Self> {
+        for (i, monkey) in monkeys.iter().enumerate() {
+            if monkey.id != i {
+                bail!("Monkey {} is defined in position {}", monkey.id, i);
+            }
+            for target in [monkey.true_monkey, monkey.false_monkey] {
+                if target == i {
+                    bail!("Monkey {} throws to itself", i);
+                }
+                if target >= monkeys.len() {
+                    bail!("Monkey {} throws to monkey {} but there are only {} monkeys", i, target, monkeys.len());
+                }
+            }
+            if monkey.test.period() <= 0 {
+                bail!("Monkey {} tests divisibility by {}", i, monkey.test.period());
+            }
+            if !monkey.inspect_operation.has_constant_exponents() {
+                bail!("Monkey {}'s operation {} has an exponent that depends on old", i, monkey.inspect_operation);
+            }
+        }
+        if relief.divisor() <= 0 {
+            bail!("Relief must divide worry by a positive number, not {}", relief.divisor());
+        }
+        let modulus = monkeys.iter().map(|m| m.test.period())
+            .try_fold(1i128, |lcm, p| (lcm / gcd(lcm, p)).checked_mul(p))
+            .and_then(|lcm| lcm.checked_mul(relief.divisor()))
+            .ok_or_else(|| eyre!("Least common multiple of the monkeys' tests is too large"))?;
+        Ok(Self { monkeys, relief, modulus, trace: false })
     }
 
-    pub fn round(&mut self, part: Part) {
+    fn with_trace(self, trace: bool) -> Self {
+        Self { trace, ..self }
+    }
+
+    pub fn round(&mut self) -> Result<()> {
         for i in 0..self.monkeys.len() {
             let (before, cur, after) = self.monkeys.split_around_mut(i).unwrap();
-            let throws = match part {
-                Part::One => cur.take_a_turn(),
-                Part::Two => cur.take_a_turn_part2(),
-            };
-            for mut throw in throws {
-                throw.item.worry %= self.reduction_constant * 3;
+            let throws = cur.take_a_turn(self.modulus, self.relief, self.trace)?;
+            for throw in throws {
                 if throw.to_monkey < i {
-                    before[throw.to_monkey].catch(throw);
+                    before[throw.to_monkey].catch(throw, self.trace);
                 } else {
                     assert!(throw.to_monkey > i);
-                    after[throw.to_monkey - i - 1].catch(throw);
+                    after[throw.to_monkey - i - 1].catch(throw, self.trace);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Which monkey holds each worry value. Items never affect each other, so the order they are
+    /// held in doesn't matter to future inspection counts
+    fn item_distribution(&self) -> Vec<(usize, i128)> {
+        let mut items: Vec<_> = self.monkeys.iter()
+            .flat_map(|m| m.held_items.iter().map(|item| (m.id, item.worry)))
+            .collect();
+        items.sort_unstable();
+        items
+    }
+
+    fn inspection_counts(&self) -> Vec<usize> {
+        self.monkeys.iter().map(|m| m.inspection_count).collect()
+    }
+
+    /// Play `rounds` rounds. Once the item distribution repeats, the inspection counts of all the
+    /// whole cycles that fit in the remaining rounds are added at once
+    pub fn play(&mut self, rounds: u64) -> Result<()> {
+        let mut detector = CycleDetector::new();
+        let mut history: Vec<Vec<usize>> = vec![];
+        let mut round = 0;
+        while round < rounds {
+            if let Some(cycle) = detector.observe(self.item_distribution()) {
+                let period = cycle.period as u64;
+                let cycles = (rounds - round) / period;
+                if self.trace {
+                    println!("Round {} repeats round {}, skipping {} cycles", round, cycle.start, cycles);
                 }
+                for (monkey, first_count) in self.monkeys.iter_mut().zip(&history[cycle.start]) {
+                    monkey.inspection_count += (monkey.inspection_count - first_count) * cycles as usize;
+                }
+                round += cycles * period;
+                break;
             }
+            history.push(self.inspection_counts());
+            if self.trace { println!("Round {}", round); }
+            self.round()?;
+            round += 1;
+        }
+        while round < rounds {
+            self.round()?;
+            round += 1;
         }
+        Ok(())
+    }
+
+    /// Product of the two highest inspection counts
+    pub fn monkey_business(&self) -> u128 {
+        self.monkeys.iter().map(|m| std::cmp::Reverse(m.inspection_count as u128)).k_smallest(2).map(|r| r.0).product()
     }
 }
 
-enum Part { One, Two }
+mod parsing {
+    use super::{Expression, Item, Monkey, Test};
+    use nom::IResult;
+    use nom::branch::alt;
+    use nom::bytes::complete::tag;
+    use nom::character::complete::{char, i64, line_ending, multispace0, multispace1, one_of, space0, u32};
+    use nom::combinator::{cut, map, value};
+    use nom::multi::{fold_many0, separated_list0, separated_list1};
+    use nom::sequence::{delimited, pair, preceded, terminated, tuple};
+
+    fn constant(s: &str) -> IResult<&str, i128> {
+        map(i64, i128::from)(s)
+    }
+
+    fn atom(s: &str) -> IResult<&str, Expression> {
+        alt((
+            value(Expression::OldValue, tag("old")),
+            map(constant, Expression::Constant),
+            delimited(pair(char('('), space0), expression, pair(space0, char(')')))
+        ))(s)
+    }
+
+    /// Exponentiation binds tightest and is right associative
+    fn power(s: &str) -> IResult<&str, Expression> {
+        let (s, base) = atom(s)?;
+        match preceded(delimited(space0, char('^'), space0), power)(s) {
+            Ok((s, exp)) => Ok((s, Expression::Pow(base.into(), exp.into()))),
+            Err(nom::Err::Error(_)) => Ok((s, base)),
+            Err(e) => Err(e)
+        }
+    }
+
+    fn term(s: &str) -> IResult<&str, Expression> {
+        let (s, first) = power(s)?;
+        fold_many0(preceded(delimited(space0, char('*'), space0), power), move || first.clone(),
+            |lhs, rhs| Expression::Mul(lhs.into(), rhs.into()))(s)
+    }
+
+    pub fn expression(s: &str) -> IResult<&str, Expression> {
+        let (s, first) = term(s)?;
+        fold_many0(pair(delimited(space0, one_of("+-"), space0), term), move || first.clone(),
+            |lhs, (op, rhs)| match op {
+                '+' => Expression::Add(lhs.into(), rhs.into()),
+                _ => Expression::Sub(lhs.into(), rhs.into()),
+            })(s)
+    }
+
+    /// A line of a monkey definition, after the line break and indent
+    fn field<'a, O>(label: &'static str, p: impl FnMut(&'a str) -> IResult<&'a str, O>) -> impl FnMut(&'a str) -> IResult<&'a str, O> {
+        preceded(tuple((line_ending, space0, tag(label), space0)), p)
+    }
+
+    fn monkey_id(s: &str) -> IResult<&str, usize> {
+        map(u32, |n| n as usize)(s)
+    }
+
+    fn monkey(s: &str) -> IResult<&str, Monkey> {
+        let (s, id) = preceded(tag("Monkey "), cut(terminated(monkey_id, char(':'))))(s)?;
+        let (s, (held_items, inspect_operation, divisor, true_monkey, false_monkey)) = cut(tuple((
+            field("Starting items:", separated_list0(tag(", "), map(constant, Item::new))),
+            field("Operation: new =", expression),
+            field("Test: divisible by", constant),
+            field("If true: throw to monkey", monkey_id),
+            field("If false: throw to monkey", monkey_id),
+        )))(s)?;
+        let test = Test::DivisibleBy(divisor);
+        Ok((s, Monkey { id, held_items, inspect_operation, test, true_monkey, false_monkey, inspection_count: 0 }))
+    }
+
+    pub fn monkeys(s: &str) -> IResult<&str, Vec<Monkey>> {
+        delimited(multispace0, separated_list1(multispace1, monkey), multispace0)(s)
+    }
+}
 
 fn main() -> Result<()> {
     color_eyre::install()?;
-    let _cli = Cli::parse();
+    let cli = Cli::parse();
+
+    let input = std::io::read_to_string(cli.input.get_input()?)?;
+    let monkeys = aoc2022::iterators::parse_all(input.as_str(), parsing::monkeys)?;
+
+    let mut game = Keepaway::new(monkeys.clone(), Relief::DivideBy(3))?.with_trace(cli.trace);
+    game.play(20)?;
 
-    use Expression::*;
-    use Test::*;
+    println!("part 1 answer is {}", game.monkey_business());
 
-    let monkeys = vec![
-        Monkey::new(0, [54, 98, 50, 94, 69, 62, 53, 85], Mul(OldValue.into(), Constant(13).into()), 3, 2, 1),
-        Monkey::new(1, [71, 55, 82], Add(OldValue.into(), 2.into()), 13, 7, 2),
-        Monkey::new(2, [77, 73, 86, 72, 87], Add(OldValue.into(), 8.into()), 19, 4, 7),
-        Monkey::new(3, [97, 91], Add(OldValue.into(), 1.into()), 17, 6, 5),
-        Monkey::new(4, [78, 97, 51, 85, 66, 63, 62], Mul(OldValue.into(), 17.into()), 5, 6, 3),
-        Monkey::new(5, [88], Add(OldValue.into(), 3.into()), 7, 1, 0),
-        Monkey::new(6, [87, 57, 63, 86, 87, 53], Mul(OldValue.into(), OldValue.into()), 11, 5, 0),
-        Monkey::new(7, [73, 59, 82, 65], Add(OldValue.into(), 6.into()), 2, 4, 3),
-    ];
+    let mut game = Keepaway::new(monkeys, Relief::None)?.with_trace(cli.trace);
+    game.play(cli.rounds)?;
+
+    println!("part 2 answer is {}", game.monkey_business());
+
+    Ok(())
+}
 
-    let mut game = Keepaway::new(monkeys.clone());
-    
-    for i in 0..20 {
-        println!("Round {}", i);
-        game.round(Part::One);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    const EXAMPLE: &str = "\
+Monkey 0:
+  Starting items: 79, 98
+  Operation: new = old * 19
+  Test: divisible by 23
+    If true: throw to monkey 2
+    If false: throw to monkey 3
+
+Monkey 1:
+  Starting items: 54, 65, 75, 74
+  Operation: new = old + 6
+  Test: divisible by 19
+    If true: throw to monkey 2
+    If false: throw to monkey 0
+
+Monkey 2:
+  Starting items: 79, 60, 97
+  Operation: new = old * old
+  Test: divisible by 13
+    If true: throw to monkey 1
+    If false: throw to monkey 3
+
+Monkey 3:
+  Starting items: 74
+  Operation: new = old + 3
+  Test: divisible by 17
+    If true: throw to monkey 0
+    If false: throw to monkey 1
+";
+
+    fn expression(s: &str) -> Expression {
+        aoc2022::iterators::parse_all(s, parsing::expression).unwrap()
     }
 
-    let answer : isize = game.monkeys.iter().map(|m| -(m.inspection_count as isize)).k_smallest(2).product();
+    #[test_case("old * 19", "(old * 19)")]
+    #[test_case("old + 2 * old", "(old + (2 * old))")]
+    #[test_case("old - 1 - 2", "((old - 1) - 2)")]
+    #[test_case("2 ^ 3 ^ 2", "(2 ^ (3 ^ 2))")]
+    #[test_case("( old + 1 ) * -3", "((old + 1) * -3)")]
+    fn parses_expressions(input: &str, expected: &str) {
+        assert_eq!(expression(input).to_string(), expected);
+    }
 
-    println!("part 1 answer is {}", answer);
+    #[test]
+    fn parses_monkeys() {
+        let monkeys = aoc2022::iterators::parse_all(EXAMPLE, parsing::monkeys).unwrap();
+        assert_eq!(monkeys.len(), 4);
+        assert_eq!(monkeys[1].held_items.iter().map(|i| i.worry).collect_vec(), vec![54, 65, 75, 74]);
+        assert_eq!(monkeys[2].inspect_operation.to_string(), "(old * old)");
+        assert_eq!((monkeys[3].test.period(), monkeys[3].true_monkey, monkeys[3].false_monkey), (17, 0, 1));
+    }
 
-    let mut game = Keepaway::new(monkeys.clone());
-    
-    for i in 0..10_000 {
-        println!("Round {}", i);
-        game.round(Part::Two);
+    #[test_case("old * old + 3", 12, 1000, 147)]
+    #[test_case("old - 20", 12, 7, 6)]
+    #[test_case("(old + 1) ^ 3", 4, 1000, 125)]
+    #[test_case("old ^ (5 - 2)", 99, 10, 9)]
+    #[test_case("3 ^ 100", 0, 7, 4)]
+    fn evaluates_modulo(input: &str, old: i128, modulus: i128, expected: i128) {
+        assert_eq!(expression(input).eval(old, modulus).unwrap(), expected);
     }
 
-    let answer : isize = game.monkeys.iter().map(|m| -(m.inspection_count as isize)).k_smallest(2).product();
+    #[test]
+    fn rejects_negative_exponents() {
+        // -1 is 6 modulo 7, so this must be caught before reducing
+        assert!(expression("old ^ (0 - 1)").eval(2, 7).is_err());
+        assert!(expression("old ^ -2").eval(2, 7).is_err());
+    }
 
-    println!("part 2 answer is {}", answer);
+    #[test]
+    fn plays_the_example() {
+        let monkeys = aoc2022::iterators::parse_all(EXAMPLE, parsing::monkeys).unwrap();
+        let mut game = Keepaway::new(monkeys.clone(), Relief::DivideBy(3)).unwrap();
+        game.play(20).unwrap();
+        assert_eq!(game.monkey_business(), 10605);
 
-    Ok(())
+        let mut game = Keepaway::new(monkeys, Relief::None).unwrap();
+        game.play(10_000).unwrap();
+        assert_eq!(game.monkey_business(), 2713310158);
+    }
 }
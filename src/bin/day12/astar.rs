@@ -7,6 +7,11 @@ pub trait SearchProblem<'a> {
     fn start(&self) -> Self::Node;
     fn goal(&self) -> Self::Node;
 
+    /// Every node the search may begin from. The cheapest route from any of them wins
+    fn starts(&self) -> Vec<Self::Node> {
+        vec![self.start()]
+    }
+
     fn distance_heuristic(&self, n: &Self::Node) -> usize;
     fn successors(&'a self, n: Self::Node) -> Self::SuccessorIter;
 }
@@ -54,20 +59,39 @@ impl<'a,P:SearchProblem<'a>> Ord for NodeCost<'a, P> {
     }
 }
 
-pub fn astar<'a,P:SearchProblem<'a>>(p: &'a P) -> Option<usize> {
+pub struct Solution<N> {
+    /// Every node on the route, from the start it began at through to the goal
+    pub path: Vec<N>,
+    pub cost: usize,
+    /// How many nodes were taken off the open list
+    pub iter_count: usize
+}
+
+pub fn astar<'a,P:SearchProblem<'a>>(p: &'a P) -> Option<Solution<P::Node>> {
     let mut iter_count = 0;
-    let mut open = BinaryHeap::from([NodeCost::new(p, p.start(), 0)]);
+    let mut open = BinaryHeap::new();
     let mut min_costs : HashMap<P::Node, usize> = HashMap::new();
-    min_costs.insert(p.start(), 0);
+    let mut came_from : HashMap<P::Node, P::Node> = HashMap::new();
+    for start in p.starts() {
+        min_costs.insert(start.clone(), 0);
+        open.push(NodeCost::new(p, start, 0));
+    }
 
     while let Some(current) = open.pop() {
         iter_count += 1;
+        if min_costs.get(&current.node).is_some_and(|cost| *cost < current.path_cost) {
+            continue;
+        }
         if current.node == p.goal() {
-            //println!("Solve search in {} iterations", iter_count);
-            return Some(current.path_cost)
+            let mut path = vec![current.node];
+            while let Some(prev) = came_from.get(path.last().unwrap()) {
+                path.push(prev.clone());
+            }
+            path.reverse();
+            return Some(Solution { path, cost: current.path_cost, iter_count })
         }
 
-        for succ in p.successors(current.node) {
+        for succ in p.successors(current.node.clone()) {
             let succ_cost = current.path_cost + 1;
             if let Some(prev_cost) = min_costs.get(&succ) {
                 if *prev_cost <= succ_cost {
@@ -75,10 +99,10 @@ pub fn astar<'a,P:SearchProblem<'a>>(p: &'a P) -> Option<usize> {
                 }
             }
             min_costs.insert(succ.clone(), succ_cost);
+            came_from.insert(succ.clone(), current.node.clone());
             open.push(NodeCost::new(p, succ, succ_cost));
         }
     }
 
-    //println!("No solution after {} iterations", iter_count);
     None
 }
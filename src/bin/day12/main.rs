@@ -1,12 +1,15 @@
 use aoc2022::prelude::*;
+use aoc2022::grid;
+use aoc2022::render::{Color, PathOverlay, RenderCLI};
 
 mod astar;
-mod grid;
 
 #[derive(Parser)]
 struct Cli {
     #[command(flatten)]
-    input: InputCLI<12>
+    input: InputCLI<12>,
+    #[command(flatten)]
+    render: RenderCLI
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -28,10 +31,57 @@ impl Square {
     }
 }
 
+impl grid::GridSquareDisplay for Square {
+    fn cell_char(&self) -> char {
+        (b'a' + self.elevation) as char
+    }
+
+    fn cell_color(&self) -> Option<Color> {
+        let low = Color::rgb(20, 60, 140);
+        let high = Color::rgb(240, 240, 240);
+        Some(low.lerp(&high, self.elevation as f32 / 25.0))
+    }
+}
+
 struct Problem {
     grid: grid::Grid<Square>,
     start_pos: grid::Coord,
-    end_pos: grid::Coord
+    end_pos: grid::Coord,
+    /// Start from every square at the lowest elevation rather than just `start_pos`
+    any_low_start: bool
+}
+
+impl Problem {
+    /// The heightmap, with `S` marking the start and `E` the end
+    fn parse(input: impl BufRead) -> Result<Self> {
+        let mut grid : grid::Grid<Square> = grid::Grid::empty();
+
+        let mut start_pos = grid::Coord { row: 0, col: 0 };
+        let mut end_pos = grid::Coord { row: 0, col: 0 };
+
+        for (row, line) in input.lines().enumerate() {
+            let line = line?;
+
+            let row : Vec<_> = line.bytes().enumerate()
+                .map(|(col, b)| {
+                    if b == b'S' {
+                        start_pos = grid::Coord { row, col };
+                        b'a'
+                    } else if b == b'E' {
+                        end_pos = grid::Coord { row, col };
+                        b'z'
+                    } else {
+                        b
+                    }
+                })
+                .map(|b| Square::from_byte(b).unwrap())
+                    .collect();
+
+            grid.push_row(row).map_err(|_| eyre!("Couldn't push row"))?;
+        }
+
+        Ok(Self { grid, start_pos, end_pos, any_low_start: false })
+    }
 }
 
 impl<'a> astar::SearchProblem<'a> for Problem {
@@ -46,6 +96,17 @@ impl<'a> astar::SearchProblem<'a> for Problem {
         self.end_pos
     }
 
+    fn starts(&self) -> Vec<Self::Node> {
+        if !self.any_low_start {
+            return vec![self.start_pos];
+        }
+        self.grid.rows().enumerate()
+            .flat_map(|(row, r)| r.iter().enumerate().map(move |(col, sq)| (grid::Coord { row, col }, sq.elevation)).collect::<Vec<_>>())
+            .filter(|(_, elevation)| *elevation == 0)
+            .map(|(coord, _)| coord)
+            .collect()
+    }
+
     fn distance_heuristic(&self, n: &Self::Node) -> usize {
         let horiz_distance = n.manhattan_distance(&self.goal());
         let vert_distance = self.grid[&self.goal()].elevation - self.grid[n].elevation;
@@ -106,53 +167,59 @@ fn main() -> Result<()> {
     color_eyre::install()?;
     let cli = Cli::parse();
 
-    let mut grid : grid::Grid<Square> = grid::Grid::empty();
+    let mut problem = Problem::parse(cli.input.get_input()?)?;
+    println!("Grid size is {}x{}", problem.grid.width(), problem.grid.height());
 
-    let mut start_pos = grid::Coord { row: 0, col: 0 };
-    let mut end_pos = grid::Coord { row: 0, col: 0 };
+    let climb = astar::astar(&problem).ok_or_else(|| eyre!("No path from start to end!"))?;
+    println!("Shortest path has length {} (searched {} squares)", climb.cost, climb.iter_count);
 
-    for (row, line) in cli.input.get_input()?.lines().enumerate() {
-        let line = line?;
+    problem.any_low_start = true;
+    let hike = astar::astar(&problem).ok_or_else(|| eyre!("No path from any low square to the end!"))?;
+    let hike_start = hike.path[0];
+    println!("shortest hike is length {} from row {} column {} (searched {} squares)",
+        hike.cost, hike_start.row, hike_start.col, hike.iter_count);
 
-        let row : Vec<_> = line.bytes().enumerate()
-            .map(|(col, b)| {
-                if b == b'S' {
-                    start_pos = grid::Coord { row, col };
-                    b'a'
-                } else if b == b'E' {
-                    end_pos = grid::Coord { row, col };
-                    b'z'
-                } else {
-                    b
-                }
-            })
-            .map(|b| Square::from_byte(b).unwrap())
-                .collect();
-
-        grid.push_row(row).map_err(|_| eyre!("Couldn't push row"))?;
-    }
-
-    println!("Grid size is {}x{}", grid.width(), grid.height());
-
-    let mut problem = Problem { grid, start_pos, end_pos };
-
-    let cost = astar::astar(&problem).ok_or_else(|| eyre!("No path from start to end!"))?;
-
-    println!("Shortest path has length {}", cost);
-
-    let mut grid = problem.grid;
-
-    let bestest = grid.rows().enumerate().flat_map(|(row, r)| r.iter().enumerate().map(move |(col, sq)| (grid::Coord { row, col }, sq)))
-        .filter_map(|(coord, sq)| {
-            if sq.elevation != 0 {
-                None
-            } else {
-                let p = Problem { grid: grid.clone(), start_pos: coord, end_pos };
-                astar::astar(&p)
-            }
-        }).min().unwrap();
-
-    println!("shortest hike is length {}", bestest);
+    cli.render.render(&problem.grid, &[
+        PathOverlay::new(hike.path, Color::GREEN),
+        PathOverlay::new(climb.path, Color::RED),
+    ])?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use itertools::Itertools;
+    use test_case::test_case;
+
+    const EXAMPLE: &str = "\
+Sabqponm
+abcryxxl
+accszExk
+acctuvwj
+abdefghi
+";
+
+    #[test_case(false => 31; "from the start")]
+    #[test_case(true => 29; "from any low square")]
+    fn shortest_path(any_low_start: bool) -> usize {
+        let mut problem = Problem::parse(EXAMPLE.as_bytes()).unwrap();
+        problem.any_low_start = any_low_start;
+        let solution = astar::astar(&problem).unwrap();
+
+        assert_eq!(solution.path.len(), solution.cost + 1);
+        assert_eq!(solution.path.last(), Some(&grid::Coord { row: 2, col: 5 }));
+        let start = solution.path[0];
+        if any_low_start {
+            assert_eq!(problem.grid[&start].elevation, 0);
+        } else {
+            assert_eq!(start, grid::Coord { row: 0, col: 0 });
+        }
+        for (from, to) in solution.path.iter().tuple_windows() {
+            assert_eq!(from.manhattan_distance(to), 1);
+            assert!(problem.grid[from].can_climb_to(problem.grid[to]));
+        }
+        solution.cost
+    }
+}
@@ -1,4 +1,5 @@
 use aoc2022::prelude::*;
+use color_eyre::eyre::WrapErr;
 use itertools::Itertools;
 
 #[derive(Parser)]
@@ -102,19 +103,17 @@ fn main() -> Result<()> {
     color_eyre::install()?;
     let cli = Cli::parse();
 
-    let mut lines_iter = cli.input.get_input()?.lines();
-
     let mut part1_answer = 0;
-    let mut index = 1;
 
     let mut all_packets: Vec<Packet> = Vec::new();
 
-    while let Some(first) = lines_iter.next() {
-        let first = first?;
-        let second = lines_iter.next().ok_or_else(|| eyre!("Missing second packet in pair"))??;
-
-        let first = Packet::parse(first.as_str()).map_err(|e| e.to_owned())?.1;
-        let second = Packet::parse(second.as_str()).map_err(|e| e.to_owned())?.1;
+    for (index, pair) in (1..).zip(cli.input.get_input()?.lines().try_paragraphs()) {
+        let (first, second) = pair?.into_iter().parse(Packet::parse)
+            .collect::<Result<Vec<_>>>()
+            .wrap_err_with(|| format!("Parsing packet pair {index}"))?
+            .into_iter()
+            .collect_tuple()
+            .ok_or_else(|| eyre!("Packet pair {index} does not have exactly two packets"))?;
 
         if first < second {
             part1_answer += index;
@@ -122,18 +121,6 @@ fn main() -> Result<()> {
 
         all_packets.push(first);
         all_packets.push(second);
-
-        index += 1;
-
-        match lines_iter.next() {
-            None => break,
-            Some(Err(e)) => return Err(e.into()),
-            Some(Ok(l)) => {
-                if !l.is_empty() {
-                    bail!("Expected blank line but was {:?}", l)
-                }
-            }
-        }
     }
 
     println!("Part 1 answer: {}", part1_answer);
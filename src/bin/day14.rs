@@ -3,12 +3,15 @@ use std::time::Duration;
 
 use aoc2022::prelude::*;
 use aoc2022::grid;
+use aoc2022::render::{Color, RenderCLI};
 use itertools::Itertools;
 
 #[derive(Parser)]
 struct Cli {
     #[command(flatten)]
-    input: InputCLI<14>
+    input: InputCLI<14>,
+    #[command(flatten)]
+    render: RenderCLI
 }
 
 #[derive(Copy, Clone, PartialEq, Eq)]
@@ -78,6 +81,14 @@ impl grid::GridSquareDisplay for Cell {
             Cell::Sand => 'o',
         }
     }
+
+    fn cell_color(&self) -> Option<Color> {
+        match self {
+            Cell::Air => Some(Color::rgb(20, 20, 40)),
+            Cell::Rock => Some(Color::GREY),
+            Cell::Sand => Some(Color::YELLOW),
+        }
+    }
 }
 
 struct SandSimulator {
@@ -159,9 +170,7 @@ fn main() -> Result<()> {
     color_eyre::install()?;
     let cli = Cli::parse();
 
-    let walls: Vec<Wall> = cli.input.get_input()?.lines()
-        .map(|r| r.map_err(Into::<Report>::into))
-            .map(|l| l.and_then(|l| Wall::parse(l.as_str()).map_err(|e| e.to_owned()).map_err(Into::into).map(|p| p.1))).try_collect()?;
+    let walls: Vec<Wall> = cli.input.get_input()?.lines().parse(Wall::parse).try_collect()?;
 
     let extent = max_extent(walls.iter().map(Wall::max_extent));
 
@@ -190,7 +199,7 @@ fn main() -> Result<()> {
             break
         }
     }
-        println!("{}", sim.grid);
+    cli.render.render_or_print(&sim.grid, &[])?;
 
     Ok(())
 }
@@ -1,16 +1,22 @@
-use std::collections::HashMap;
-use std::collections::HashSet;
-use std::ops::RangeBounds;
 use std::ops::RangeInclusive;
 
 use aoc2022::prelude::*;
-use aoc2022::grid;
+use aoc2022::intervals::IntervalSet;
 use itertools::Itertools;
 
 #[derive(Parser)]
 struct Cli {
     #[command(flatten)]
-    input: InputCLI<15>
+    input: InputCLI<15>,
+    /// Row to count the positions that can't hold a beacon in
+    #[arg(long, default_value_t = 2000000)]
+    row: i32,
+    /// Smallest x and y the distress beacon can have
+    #[arg(long, default_value_t = 0)]
+    min_coord: i32,
+    /// Largest x and y the distress beacon can have
+    #[arg(long, default_value_t = 4000000)]
+    max_coord: i32,
 }
 
 #[derive(PartialEq, Eq, Hash, Debug, Copy, Clone)]
@@ -19,10 +25,6 @@ struct Position {
     y: i32
 }
 
-fn merge_inclusive_ranges<T:Ord + Clone>(a: &RangeInclusive<T>, b: &RangeInclusive<T>) -> RangeInclusive<T> {
-    RangeInclusive::new(a.start().min(b.start()).clone(), a.end().max(b.end()).clone())
-}
-
 impl Position {
     pub fn parse(s: &str) -> nom::IResult<&str, Position> {
         use nom::sequence::{preceded, separated_pair};
@@ -67,97 +69,68 @@ impl SensorReport {
         self.position.manhattan_distance(&self.closest_beacon)
     }
 
-    fn non_beacon_positions_in_row(&self, row: i32) -> impl Iterator<Item = Position> {
-        let col_range = self.non_beacon_range_in_row(row);
-        col_range.map(move |x| Position { x, y: row })
+    /// Whether this sensor rules out a beacon at `position`
+    fn covers(&self, position: &Position) -> bool {
+        self.position.manhattan_distance(position) <= self.distance_to_beacon()
+    }
+
+    /// The diagonal lines just outside this sensor's diamond, as the constant `x + y` of the two
+    /// rising lines and the constant `x - y` of the two falling ones
+    fn boundary_diagonals(&self) -> ([i64; 2], [i64; 2]) {
+        let (x, y, r) = (self.position.x as i64, self.position.y as i64, self.distance_to_beacon() as i64 + 1);
+        ([x + y - r, x + y + r], [x - y - r, x - y + r])
     }
 
-    fn non_beacon_range_in_row(&self, row: i32) -> RangeInclusive<i32> {
+    fn non_beacon_range_in_row(&self, row: i32) -> Option<RangeInclusive<i32>> {
         let beacon_distance = self.position.manhattan_distance(&self.closest_beacon);
         let row_distance = i32::abs_diff(self.position.y, row);
-        if row_distance <= beacon_distance {
+        (row_distance <= beacon_distance).then(|| {
             let col_deviation = beacon_distance - row_distance;
             (self.position.x-(col_deviation as i32))..=(self.position.x+(col_deviation as i32))
-        } else {
-            1..=0
-        }
+        })
     }
 }
 
-#[derive(Debug)]
-struct IntervalSet<T> {
-    intervals: Vec<RangeInclusive<T>>
+/// Positions in `row` that can't hold a beacon, not counting beacons already found there
+fn non_beacon_count(reports: &[SensorReport], row: i32) -> u128 {
+    let ruled_out: IntervalSet<i32> = reports.iter().filter_map(|r| r.non_beacon_range_in_row(row)).collect();
+    let beacons: IntervalSet<i32> = reports.iter()
+        .map(|r| r.closest_beacon)
+        .filter(|b| b.y == row)
+        .map(|b| b.x..=b.x)
+        .collect();
+    ruled_out.difference(&beacons).len()
 }
 
-impl<T:Ord + Clone> IntervalSet<T> {
-    fn new() -> Self {
-        Self { intervals: Vec::new() }
-    }
-
-    fn add(&mut self, interval: RangeInclusive<T>) {
-        if self.intervals.is_empty() {
-            self.intervals.push(interval)
-        } else {
-            let left_edge = self.intervals.partition_point(|existing| existing.end() < interval.start());
-            // intervals[left_edge-1].end < interval.start
-            let right_edge = self.intervals.partition_point(|existing| existing.start() <= interval.end());
-            // intervals[right_edge].start() > interval.end
-            // intervals[left_edge..right_edge] will be replaced
-            let replaced_range = left_edge..right_edge;
-            if replaced_range.is_empty() {
-                // just insert the interval
-                self.intervals.insert(left_edge, interval);
-            } else if replaced_range.len() == 1 {
-                let previous = &self.intervals[left_edge];
-                if previous.contains(interval.start()) && previous.contains(interval.end()) {
-                    // interval already in the set
-                    return;
-                } else {
-                    // need to replace previous by something bigger
-                    let replacement = RangeInclusive::new(previous.start().min(interval.start()).clone(), previous.end().max(interval.end()).clone());
-                    self.intervals[left_edge] = replacement;
-                }
-            } else {
-                let first = &self.intervals[left_edge];
-                let last = &self.intervals[right_edge-1];
-                let replacement =
-                    if interval.contains(first.start()) && interval.contains(last.end()) {
-                        interval
-                    } else {
-                        let start = first.start().min(interval.start()).clone();
-                        let end = last.end().max(interval.end()).clone();
-                        start..=end
-                    };
-                self.intervals.splice(replaced_range, std::iter::once(replacement));
-            }
-        }
-    }
-
-    fn contains(&self, point: &T) -> bool {
-        if let Some(candidate) = self.intervals.get(self.intervals.partition_point(|i| i.start() < point)) {
-            candidate.contains(point)
-        } else {
-            false
-        }
-    }
-
-    fn contains_range(&self, range: &RangeInclusive<T>) -> bool {
-        if let Some(candidate) = self.intervals.get(self.intervals.partition_point(|i| i.start() < range.start())) {
-            candidate.contains(range.start()) && candidate.contains(range.end())
-        } else {
-            false
-        }
-    }
-
-    fn gaps<'a>(&'a self) -> impl Iterator<Item=RangeInclusive<T>> + 'a
-        where T: std::ops::Add<Output=T> + std::ops::Sub<Output=T>,
-              i32: Into<T>
-    {
-        self.intervals.iter()
-            .tuple_windows()
-            .map(|(a,b)| RangeInclusive::new(a.end().clone()+1.into(), b.start().clone()-1.into()))
-            .filter(|g| !g.is_empty())
+/// The one position within `bounds` on both axes that no sensor covers. If it's unique, it's
+/// pinned in place either by two diamonds' boundary diagonals or by one and an edge of the
+/// bounds, so only those crossings need checking
+fn distress_beacon(reports: &[SensorReport], bounds: RangeInclusive<i32>) -> Option<Position> {
+    let (lo, hi) = (*bounds.start() as i64, *bounds.end() as i64);
+    let (mut sums, mut differences): (Vec<i64>, Vec<i64>) = (vec![], vec![]);
+    for report in reports {
+        let (s, d) = report.boundary_diagonals();
+        sums.extend(s);
+        differences.extend(d);
     }
+    // the edges of the bounds, as where they cross each diagonal
+    let edges = |sum: Option<i64>, difference: Option<i64>| [lo, hi].into_iter().flat_map(move |edge| {
+        let on_x_edge = sum.map(|s| (edge, s - edge)).or(difference.map(|d| (edge, edge - d)));
+        let on_y_edge = sum.map(|s| (s - edge, edge)).or(difference.map(|d| (d + edge, edge)));
+        on_x_edge.into_iter().chain(on_y_edge)
+    });
+
+    let crossings = sums.iter().cartesian_product(differences.iter())
+        .filter(|(s, d)| (*s - *d) % 2 == 0)
+        .map(|(s, d)| ((s + d) / 2, (s - d) / 2))
+        .chain(sums.iter().flat_map(|s| edges(Some(*s), None)))
+        .chain(differences.iter().flat_map(|d| edges(None, Some(*d))))
+        .chain([(lo, lo), (lo, hi), (hi, lo), (hi, hi)]);
+
+    crossings
+        .filter(|(x, y)| (lo..=hi).contains(x) && (lo..=hi).contains(y))
+        .map(|(x, y)| Position { x: x as i32, y: y as i32 })
+        .find(|p| !reports.iter().any(|r| r.covers(p)))
 }
 
 fn main() -> Result<()> {
@@ -170,30 +143,56 @@ fn main() -> Result<()> {
         .map(|l: Result<String>| l.and_then(|l| SensorReport::parse(l.as_str()).map(|p| p.1).map_err(|e| e.to_owned().into())))
         .try_collect()?;
 
-    let mut common_positions : HashSet<Position> = reports.iter()
-        .flat_map(|r| r.non_beacon_positions_in_row(2000000))
-        .collect();
+    println!("{} positions don't contain beacon", non_beacon_count(&reports, cli.row));
 
-    reports.iter().for_each(|r| { common_positions.remove(&r.closest_beacon); });
-
-    println!("{} positions don't contain beacon", common_positions.len());
-
-    for row in (0..=4000000).rev() {
-        let mut non_beacon_cols = IntervalSet::new();
-        reports.iter()
-            .map(|report| report.non_beacon_range_in_row(row))
-            .for_each(|range| non_beacon_cols.add(range));
-        let mut gaps = non_beacon_cols.gaps()
-            .filter(|r| r.start() >= &0 && r.end() <= &4000000);
-        if let Some(gap) = gaps.next()
-        {
-            let col = *gap.start() as usize;
-            println!("Distress beacon is at x={}, y={}", col, row);
-            let freq = col * 4000000 + row as usize;
-            println!("Frequencey is {}", freq);
-            break;
-        }
-    }
+    let beacon = distress_beacon(&reports, cli.min_coord..=cli.max_coord)
+        .ok_or_else(|| eyre!("Every position from {} to {} is covered", cli.min_coord, cli.max_coord))?;
+    println!("Distress beacon is at x={}, y={}", beacon.x, beacon.y);
+    let freq = beacon.x as i64 * 4000000 + beacon.y as i64;
+    println!("Frequencey is {}", freq);
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+Sensor at x=2, y=18: closest beacon is at x=-2, y=15
+Sensor at x=9, y=16: closest beacon is at x=10, y=16
+Sensor at x=13, y=2: closest beacon is at x=15, y=3
+Sensor at x=12, y=14: closest beacon is at x=10, y=16
+Sensor at x=10, y=20: closest beacon is at x=10, y=16
+Sensor at x=14, y=17: closest beacon is at x=10, y=16
+Sensor at x=8, y=7: closest beacon is at x=2, y=10
+Sensor at x=2, y=0: closest beacon is at x=2, y=10
+Sensor at x=0, y=11: closest beacon is at x=2, y=10
+Sensor at x=20, y=14: closest beacon is at x=25, y=17
+Sensor at x=17, y=20: closest beacon is at x=21, y=22
+Sensor at x=16, y=7: closest beacon is at x=15, y=3
+Sensor at x=14, y=3: closest beacon is at x=15, y=3
+Sensor at x=20, y=1: closest beacon is at x=15, y=3
+";
+
+    fn example() -> Vec<SensorReport> {
+        EXAMPLE.lines().map(|l| SensorReport::parse(l).unwrap().1).collect()
+    }
+
+    #[test]
+    fn counts_row() {
+        assert_eq!(non_beacon_count(&example(), 10), 26);
+    }
+
+    #[test]
+    fn finds_distress_beacon() {
+        assert_eq!(distress_beacon(&example(), 0..=20), Some(Position { x: 14, y: 11 }));
+    }
+
+    #[test]
+    fn finds_beacon_in_a_corner() {
+        // one sensor covering everything but the bottom right corner
+        let reports = [SensorReport { position: Position { x: 0, y: 0 }, closest_beacon: Position { x: 0, y: 19 } }];
+        assert_eq!(distress_beacon(&reports, 0..=10), Some(Position { x: 10, y: 10 }));
+    }
+}
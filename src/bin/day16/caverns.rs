@@ -1,5 +1,7 @@
 use std::collections::HashMap;
+use std::io::Write;
 
+use color_eyre::eyre::{Result, eyre};
 use petgraph::prelude::*;
 
 #[derive(Debug)]
@@ -115,4 +117,51 @@ impl<'a> CavernGraph<'a> {
     pub fn shortest_paths(&'a self) -> HashMap<(NodeIndex<u32>, NodeIndex<u32>), u32> {
         petgraph::algo::floyd_warshall(&self.graph, |e| 1).unwrap()
     }
+
+    /// Rooms are added in order, so a room's node index is its id
+    fn node(&self, room_id: usize) -> NodeIndex<u32> {
+        NodeIndex::new(room_id)
+    }
+
+    /// Every room along the shortest walk through `stops`, in order. Fails if there's no way
+    /// from one stop to the next
+    pub fn walk(&self, stops: &[usize]) -> Result<Vec<usize>> {
+        let mut rooms = stops.first().copied().into_iter().collect::<Vec<_>>();
+        for (from, to) in stops.iter().zip(stops.iter().skip(1)) {
+            let (_, path) = petgraph::algo::astar(&self.graph, self.node(*from), |n| n == self.node(*to), |_| 1, |_| 0)
+                .ok_or_else(|| eyre!("No tunnels lead from {} to {}", self.graph[self.node(*from)].1.name(), self.graph[self.node(*to)].1.name()))?;
+            rooms.extend(path.into_iter().skip(1).map(|n| self.graph[n].0));
+        }
+        Ok(rooms)
+    }
+
+    /// Write the caverns as an undirected Graphviz graph labelled with valve rates, with each
+    /// route through `stops` drawn over the tunnels in its own colour
+    pub fn write_dot(&self, routes: &[Vec<usize>], out: &mut impl Write) -> Result<()> {
+        const COLOURS: [&str; 6] = ["red", "blue", "darkgreen", "orange", "purple", "brown"];
+
+        writeln!(out, "graph caverns {{")?;
+        writeln!(out, "    node [shape=circle];")?;
+        for (id, room) in self.graph.node_weights() {
+            let shape = if room.valve_rate() > 0 { "doublecircle" } else { "circle" };
+            writeln!(out, "    n{} [label=\"{}\\n{}\", shape={}];", id, room.name(), room.valve_rate(), shape)?;
+        }
+        for edge in self.graph.edge_references() {
+            // tunnels go both ways, so only draw each once
+            let (from, to) = (self.graph[edge.source()].0, self.graph[edge.target()].0);
+            if from < to || self.graph.find_edge(edge.target(), edge.source()).is_none() {
+                writeln!(out, "    n{} -- n{} [color=grey];", from, to)?;
+            }
+        }
+        for (agent, stops) in routes.iter().enumerate() {
+            let colour = COLOURS[agent % COLOURS.len()];
+            let rooms = self.walk(stops)?;
+            for (from, to) in rooms.iter().zip(rooms.iter().skip(1)) {
+                writeln!(out, "    n{} -- n{} [color={}, penwidth=3, dir=forward, label=\"{}\", fontcolor={}];",
+                    from, to, colour, agent + 1, colour)?;
+            }
+        }
+        writeln!(out, "}}")?;
+        Ok(())
+    }
 }
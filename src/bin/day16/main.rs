@@ -1,83 +1,172 @@
+use std::path::{Path, PathBuf};
+
 use aoc2022::prelude::*;
-use itertools::{process_results, Itertools};
-use petgraph::{prelude::*, visit::IntoNodeIdentifiers};
-use std::collections::HashMap;
+use itertools::Itertools;
 
 mod caverns;
+mod plan;
 mod statespace;
-mod statespace2;
+mod subsets;
 
 use caverns::*;
+use plan::*;
 
 #[derive(Parser)]
 struct Cli {
     #[command(flatten)]
-    input: InputCLI<16>
+    input: InputCLI<16>,
+    /// Minutes until the volcano erupts
+    #[arg(long, default_value_t = 30)]
+    time_budget: u32,
+    /// Room every agent starts in
+    #[arg(long, default_value = "AA")]
+    start_room: String,
+    /// Numbers of agents to find the best relief for
+    #[arg(long, value_delimiter = ',', default_values_t = [1, 2])]
+    agents: Vec<usize>,
+    /// Minutes spent teaching each agent after the first
+    #[arg(long, default_value_t = 4)]
+    training_time: u32,
+    #[arg(long, value_enum, default_value = "dp")]
+    solver: Solver,
+    /// Write the caverns with each agent's route as a Graphviz file. With several numbers of
+    /// agents, the number goes before the extension
+    #[arg(long)]
+    dot: Option<PathBuf>,
 }
 
-/*
-impl State {
-    fn room<'a>(&self, caverns: &'a Caverns) -> &'a Room {
-        &caverns.rooms[self.room_id]
-    }
-    fn successors(&self, caverns: &Caverns) -> impl Iterator<Item = (Action, State)> {
-        let time = self.time + 1;
-        let moves = caverns.neighbors(self.room(caverns)).map(move |room| {
-            let room = &caverns.rooms[adj];
-            let open_valves = self.open_valves.clone();
-            (Action::Move, State { time, room, open_valves })
-        });
-        let open =
-            std::iter::once(self.room).filter(|r| !self.open_valves.contains(r.name.as_str())).map(move |room| {
-                let mut open_valves = self.open_valves.clone();
-                open_valves.insert(self.room.name.as_str());
-                (Action::OpenValve, State { time, room, open_valves })
-            });
-        open.chain(moves)
-    }
+#[derive(Copy, Clone, Debug, PartialEq, Eq, clap::ValueEnum)]
+enum Solver {
+    /// Search the state space with petgraph's A*
+    Astar,
+    /// Tabulate the best relief for every set of valves
+    Dp,
 }
-*/
 
-fn main() -> Result<()> {
-    color_eyre::install()?;
-    let cli = Cli::parse();
+/// Minutes that pass teaching every agent after the first before anyone sets off
+fn start_time(cli: &Cli, agents: usize) -> u32 {
+    cli.training_time * (agents as u32).saturating_sub(1)
+}
 
-    let mut rooms = vec![];
+/// Find the most pressure `N` agents can relieve by searching the state space
+fn solve<const N: usize>(caverns: &Caverns, cli: &Cli) -> Result<Plan> {
+    let statespace = statespace::StateSpace::<N>::new(caverns, &cli.start_room, cli.time_budget, start_time(cli, N))?;
 
-    for line in cli.input.get_input()?.lines() {
-        let line = line?;
+    let (cost, path) = petgraph::algo::astar(&statespace, *statespace.initial_state(), |st| statespace.is_goal(&st), |e| e.cost, |st| statespace.path_heuristic(&st))
+        .ok_or_else(|| eyre!("No way to reach minute {}", cli.time_budget))?;
 
-        let room = Room::parse(&line).map_err(|e| e.to_owned())?.1;
-        rooms.push(room);
-    }
+    let plan = statespace.plan(&path)?;
+    debug_assert_eq!(plan.relief(caverns), statespace.max_score() - cost);
+    Ok(plan)
+}
 
-    let caverns = caverns::Caverns::new(rooms);
-    let statespace = statespace::StateSpace::new(&caverns);
+/// Find the most pressure `agents` agents can relieve from the best relief for each valve subset
+fn solve_subsets(caverns: &Caverns, cli: &Cli, agents: usize) -> Result<Plan> {
+    Ok(subsets::SubsetSolver::new(caverns, &cli.start_room, cli.time_budget, start_time(cli, agents))?.plan(agents))
+}
+
+fn write_dot(caverns: &Caverns, plan: &Plan, path: &Path) -> Result<()> {
+    let routes = (0..plan.agents).map(|agent| plan.stops(agent)).collect_vec();
+    let mut out = std::io::BufWriter::new(std::fs::File::create(path)?);
+    caverns.build_graph().write_dot(&routes, &mut out)?;
+    Ok(())
+}
 
-    // let costs = petgraph::algo::dijkstra(&statespace, *statespace.initial_state(), None, |e| e.source().pressure_buildup(&caverns));
-    //
+fn main() -> Result<()> {
+    color_eyre::install()?;
+    let cli = Cli::parse();
 
-    let (cost, path) = petgraph::algo::astar(&statespace, *statespace.initial_state(), |st| st.time() == 30, |e| e.source().pressure_buildup(&caverns) * (e.target().time() - e.source().time()) , |st| st.path_heuristic(&caverns)).unwrap();
+    let rooms: Vec<Room> = cli.input.get_input()?.lines().parse(Room::parse).try_collect()?;
 
-    let relief = statespace.max_score() - cost;
+    let caverns = caverns::Caverns::new(rooms);
 
-    for state in path.iter() {
-        println!("{:?}", state);
+    for agents in &cli.agents {
+        let plan = match (cli.solver, agents) {
+            (Solver::Astar, 1) => solve::<1>(&caverns, &cli)?,
+            (Solver::Astar, 2) => solve::<2>(&caverns, &cli)?,
+            (Solver::Astar, 3) => solve::<3>(&caverns, &cli)?,
+            (Solver::Astar, 4) => solve::<4>(&caverns, &cli)?,
+            (Solver::Astar, _) => bail!("The A* solver handles between 1 and 4 agents"),
+            (Solver::Dp, 0) => bail!("There must be at least one agent"),
+            (Solver::Dp, _) => solve_subsets(&caverns, &cli, *agents)?,
+        };
+        plan.write_schedule(&caverns, &mut std::io::stdout())?;
+        println!("Pressure relief with {} agents is {}", agents, plan.relief(&caverns));
+
+        if let Some(path) = &cli.dot {
+            let path = if cli.agents.len() > 1 {
+                let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+                match path.extension() {
+                    Some(ext) => path.with_file_name(format!("{}-{}.{}", stem, agents, ext.to_string_lossy())),
+                    None => path.with_file_name(format!("{}-{}", stem, agents)),
+                }
+            } else {
+                path.clone()
+            };
+            write_dot(&caverns, &plan, &path)?;
+        }
     }
 
-    println!("Pressure relief is {}", relief);
-
-    let statespace2 = statespace2::StateSpace::new(&caverns);
+    Ok(())
+}
 
-    let (cost, path) = petgraph::algo::astar(&statespace2, *statespace2.initial_state(), |st| st.min_time() == 30 && st.max_time() == 30, |e| e.pressure_buildup(&statespace2), |st| st.path_heuristic(&caverns)).unwrap();
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    const EXAMPLE: &str = "\
+Valve AA has flow rate=0; tunnels lead to valves DD, II, BB
+Valve BB has flow rate=13; tunnels lead to valves CC, AA
+Valve CC has flow rate=2; tunnels lead to valves DD, BB
+Valve DD has flow rate=20; tunnels lead to valves CC, AA, EE
+Valve EE has flow rate=3; tunnels lead to valves FF, DD
+Valve FF has flow rate=0; tunnels lead to valves EE, GG
+Valve GG has flow rate=0; tunnels lead to valves FF, HH
+Valve HH has flow rate=22; tunnel leads to valve GG
+Valve II has flow rate=0; tunnels lead to valves AA, JJ
+Valve JJ has flow rate=21; tunnel leads to valve II
+";
+
+    fn example() -> Caverns {
+        Caverns::new(EXAMPLE.lines().map(|line| Room::parse(line).unwrap().1))
+    }
 
-    let relief = statespace2.max_score() - cost;
+    fn cli(solver: Solver) -> Cli {
+        Cli::parse_from(["day16", "--solver", if solver == Solver::Dp { "dp" } else { "astar" }])
+    }
 
-    for state in path.iter() {
-        println!("{:?}", state);
+    #[test_case(Solver::Astar)]
+    #[test_case(Solver::Dp)]
+    fn one_agent(solver: Solver) {
+        let caverns = example();
+        let relief = match solver {
+            Solver::Astar => solve::<1>(&caverns, &cli(solver)),
+            Solver::Dp => solve_subsets(&caverns, &cli(solver), 1)
+        };
+        assert_eq!(relief.unwrap().relief(&caverns), 1651);
     }
 
-    println!("Pressure relief is {}", relief);
+    #[test_case(Solver::Astar)]
+    #[test_case(Solver::Dp)]
+    fn with_the_elephant(solver: Solver) {
+        let caverns = example();
+        let relief = match solver {
+            Solver::Astar => solve::<2>(&caverns, &cli(solver)),
+            Solver::Dp => solve_subsets(&caverns, &cli(solver), 2)
+        };
+        assert_eq!(relief.unwrap().relief(&caverns), 1707);
+    }
 
-    Ok(())
+    #[test_case(Solver::Astar)]
+    #[test_case(Solver::Dp)]
+    fn ignores_valves_that_cant_be_reached(solver: Solver) {
+        let caverns = Caverns::new(EXAMPLE.lines().chain(["Valve KK has flow rate=5; tunnel leads to valve KK"])
+            .map(|line| Room::parse(line).unwrap().1));
+        let relief = match solver {
+            Solver::Astar => solve::<1>(&caverns, &cli(solver)),
+            Solver::Dp => solve_subsets(&caverns, &cli(solver), 1)
+        };
+        assert_eq!(relief.unwrap().relief(&caverns), 1651);
+    }
 }
@@ -0,0 +1,54 @@
+use std::io::{self, Write};
+
+use crate::caverns::*;
+
+/// One agent opening one valve. The valve starts relieving pressure the minute after
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Opening {
+    pub minute: u32,
+    pub agent: usize,
+    pub room_id: usize,
+}
+
+/// Who opens which valve when
+#[derive(Clone, Debug)]
+pub struct Plan {
+    pub agents: usize,
+    pub start_room: usize,
+    pub time_budget: u32,
+    /// In order of time
+    pub openings: Vec<Opening>,
+}
+
+impl Plan {
+    pub fn new(agents: usize, start_room: usize, time_budget: u32, mut openings: Vec<Opening>) -> Self {
+        openings.sort_by_key(|o| (o.minute, o.agent));
+        Self { agents, start_room, time_budget, openings }
+    }
+
+    pub fn relief(&self, caverns: &Caverns) -> u32 {
+        self.openings.iter().map(|o| caverns.room(o.room_id).valve_rate() * (self.time_budget - o.minute)).sum()
+    }
+
+    /// The start room followed by every room `agent` opens a valve in
+    pub fn stops(&self, agent: usize) -> Vec<usize> {
+        std::iter::once(self.start_room)
+            .chain(self.openings.iter().filter(|o| o.agent == agent).map(|o| o.room_id))
+            .collect()
+    }
+
+    /// One line per valve opened, with the total flow once it's open and the pressure released
+    /// by everything opened so far
+    pub fn write_schedule(&self, caverns: &Caverns, out: &mut impl Write) -> io::Result<()> {
+        let mut flow = 0;
+        let mut released = 0;
+        for opening in &self.openings {
+            let room = caverns.room(opening.room_id);
+            flow += room.valve_rate();
+            released += room.valve_rate() * (self.time_budget - opening.minute);
+            writeln!(out, "minute {}: agent {} opens {}, +{}/min ({}/min in total, {} released by minute {})",
+                opening.minute, opening.agent + 1, room.name(), room.valve_rate(), flow, released, self.time_budget)?;
+        }
+        Ok(())
+    }
+}
@@ -1,119 +1,99 @@
 use aoc2022::bitset::BitSet;
-use itertools::Itertools;
+use aoc2022::prelude::*;
 use crate::caverns::*;
-use std::collections::{HashSet, HashMap};
+use crate::plan::*;
+use itertools::Itertools;
+use std::collections::HashSet;
 use petgraph::{prelude::*, visit::{IntoNodeReferences, NodeRef}};
 
+/// Where one agent is and how much of the time budget it has used
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct AgentState {
+    pub time: u32,
+    pub room_id: usize,
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Action {
-    Move,
-    OpenValve,
-    Wait { ticks: u32 },
+    /// Walk to a room and open its valve
+    OpenValve { room_id: usize },
+    /// Stand still until the time runs out
+    Wait,
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
-pub struct State {
-    pub time: u32,
-    pub room_id: usize,
+pub struct State<const N: usize> {
+    /// Kept sorted so that the agent with the least time used comes first, and so that swapping
+    /// two agents gives the same state
+    pub agents: [AgentState; N],
+    /// Valves opened or being walked to, by position in `StateSpace::valve_room_ids`
     pub open_valves: u64
 }
 
-impl State {
-
-    pub fn time(&self) -> u32 { self.time }
-    pub fn room_id(&self) -> usize { self.room_id }
-
-    pub fn can_open_valve(&self) -> bool {
-        !self.open_valves.contains(self.room_id as u32)
-    }
-
-    pub fn move_to(&self, space: &StateSpace, next_room: usize) -> State {
-        let mut open_valves = self.open_valves.clone();
-        open_valves.insert(next_room as u32);
-        State {
-            time: self.time + 1 + space.path_lengths[&(space.room_node_ids[self.room_id], space.room_node_ids[next_room])],
-            room_id: next_room,
-            open_valves,
-        }
-    }
-
-    pub fn open_valve(&self) -> State {
-        assert!(self.can_open_valve());
-        let mut open_valves = self.open_valves.clone();
-        open_valves.insert(self.room_id as u32);
-        State {
-            time: self.time + 1,
-            room_id: self.room_id,
-            open_valves
-        }
-    }
-
-    pub fn move_to_step(&self, space: &StateSpace, next_room: usize) -> Step {
-        let from = self.clone();
-        let to = self.move_to(space, next_room);
-        let action = Action::Move;
-        Step { from, to, action }
-    }
-
-    pub fn open_valve_step(&self) -> Step {
-        let from = self.clone();
-        let to = self.open_valve();
-        let action = Action::OpenValve;
-        Step { from, to, action }
+impl<const N: usize> State<N> {
+    /// The agent that acts next
+    pub fn active_agent(&self) -> &AgentState {
+        &self.agents[0]
     }
 
-    pub fn wait(&self, ticks: u32) -> State {
-        State {
-            time: self.time + ticks,
-            room_id: self.room_id,
-            open_valves: self.open_valves
-        }
+    pub fn min_time(&self) -> u32 {
+        self.agents[0].time
     }
 
-    pub fn wait_step(&self, ticks: u32) -> Step {
-        let from = self.clone();
-        let to = self.wait(ticks);
-        let action = Action::Wait { ticks };
-        Step { from, to, action }
+    pub fn max_time(&self) -> u32 {
+        self.agents[N - 1].time
     }
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
-pub struct Step {
-    pub from: State,
-    pub to: State,
+pub struct Step<const N: usize> {
+    pub from: State<N>,
+    pub to: State<N>,
     pub action: Action,
+    /// The acting agent before and after the step
+    pub agent_from: AgentState,
+    pub agent_to: AgentState,
+    /// Relief lost by this step compared to every valve being open from the start
+    pub cost: u32,
 }
 
-const ERUPTION_TIME : u32 = 30;
-
-pub struct StateSpace<'a> {
+pub struct StateSpace<'a, const N: usize> {
     caverns: &'a Caverns,
-    cavern_graph: CavernGraph<'a>,
-    path_lengths: HashMap<(NodeIndex<u32>, NodeIndex<u32>), u32>,
-    room_node_ids: Vec<NodeIndex<u32>>,
+    /// Minutes to walk between any two rooms, by room id
+    travel_times: Vec<Vec<u32>>,
     valve_room_ids: Vec<usize>,
-    initial_state: State,
+    start_time: u32,
+    time_budget: u32,
+    initial_state: State<N>,
     max_score: u32,
 }
 
-impl<'a> StateSpace<'a> {
-    pub fn new(caverns: &'a Caverns) -> Self {
-        let max_score = caverns.rooms().map(|room| room.valve_rate() * ERUPTION_TIME).sum();
-        let initial_state = {
-            let room_id = caverns.room_id("AA").expect("Room named AA");
-            let time = 0;
-            let open_valves = 0;
-            State { time, room_id, open_valves }
-        };
+impl<'a, const N: usize> StateSpace<'a, N> {
+    /// `N` agents start in `start_room` after `start_time` minutes, and the volcano erupts after
+    /// `time_budget` minutes
+    pub fn new(caverns: &'a Caverns, start_room: &str, time_budget: u32, start_time: u32) -> Result<Self> {
+        let room_id = caverns.room_id(start_room).ok_or_else(|| eyre!("There's no room named {}", start_room))?;
+        if start_time > time_budget {
+            bail!("Starting at minute {} leaves no time before minute {}", start_time, time_budget);
+        }
+        let valve_room_ids: Vec<usize> = caverns.rooms().enumerate().filter_map(|(id, r)| if r.valve_rate() > 0 { Some(id) } else { None }).collect();
+        if valve_room_ids.len() > u64::BITS as usize {
+            bail!("Can't track more than {} valves", u64::BITS);
+        }
+
         let cavern_graph = caverns.build_graph();
-        let valve_room_ids = caverns.rooms().enumerate().filter_map(|(id, r)| if r.valve_rate() > 0 { Some(id) } else { None }).collect();
         let path_lengths = cavern_graph.shortest_paths();
         let mut room_node_ids = vec![NodeIndex::end(); cavern_graph.graph().node_count()];
         cavern_graph.graph().node_references().for_each(|noderef| {
             room_node_ids[noderef.weight().0] = noderef.id();
         });
-        Self { max_score, caverns, cavern_graph, valve_room_ids, initial_state, path_lengths, room_node_ids }
+        let travel_times = room_node_ids.iter()
+            .map(|from| room_node_ids.iter().map(|to| path_lengths[&(*from, *to)]).collect())
+            .collect();
+
+        let initial_state = State { agents: [AgentState { time: start_time, room_id }; N], open_valves: 0 };
+        let max_score = caverns.rooms().map(|room| room.valve_rate() * (time_budget - start_time)).sum();
+        Ok(Self { caverns, travel_times, valve_room_ids, start_time, time_budget, initial_state, max_score })
     }
 
     pub fn caverns(&self) -> &'a Caverns {
@@ -124,94 +104,201 @@ impl<'a> StateSpace<'a> {
         self.valve_room_ids.as_slice()
     }
 
-    pub fn initial_state(&self) -> &State {
+    pub fn initial_state(&self) -> &State<N> {
         &self.initial_state
     }
 
+    /// The relief if every valve were open from the start. Path costs are measured down from this
     pub fn max_score(&self) -> u32 {
         self.max_score
     }
-    
-    pub fn node_for_id(&self, room_id: usize) -> NodeIndex<u32> {
-        self.room_node_ids[room_id]
+
+    pub fn time_budget(&self) -> u32 {
+        self.time_budget
     }
 
+    /// `u32::MAX` if there's no way from one room to the other
     pub fn travel_time(&self, from_room_id: usize, to_room_id: usize) -> u32 {
-        self.path_lengths[&(self.node_for_id(from_room_id), self.node_for_id(to_room_id))]
+        self.travel_times[from_room_id][to_room_id]
+    }
+
+    fn valve_rate(&self, valve: u32) -> u32 {
+        self.caverns.room(self.valve_room_ids[valve as usize]).valve_rate()
+    }
+
+    fn closed_valves(&self, state: &State<N>) -> impl Iterator<Item = u32> {
+        let open_valves = state.open_valves;
+        (0..self.valve_room_ids.len() as u32).filter(move |valve| !open_valves.contains(*valve))
+    }
+
+    pub fn is_goal(&self, state: &State<N>) -> bool {
+        state.min_time() == self.time_budget
+    }
+
+    fn steps_from(&self, from: State<N>) -> Vec<Step<N>> {
+        if self.is_goal(&from) { return vec![] }
+        let agent_from = *from.active_agent();
+        let step = |agent_to: AgentState, open_valves: u64, action: Action, cost: u32| {
+            let mut to = State { agents: from.agents, open_valves };
+            to.agents[0] = agent_to;
+            to.agents.sort();
+            Step { from, to, action, agent_from, agent_to, cost }
+        };
+
+        let mut steps: Vec<Step<N>> = self.closed_valves(&from).filter_map(|valve| {
+            let room_id = self.valve_room_ids[valve as usize];
+            let time = agent_from.time.saturating_add(self.travel_time(agent_from.room_id, room_id)).saturating_add(1);
+            (time < self.time_budget).then(|| {
+                let mut open_valves = from.open_valves;
+                open_valves.insert(valve);
+                let cost = self.valve_rate(valve) * (time - self.start_time);
+                step(AgentState { time, room_id }, open_valves, Action::OpenValve { room_id }, cost)
+            })
+        }).collect();
+
+        // once the last agent stops, every valve still closed has lost all of its relief
+        let last_agent = from.agents[1..].iter().all(|a| a.time == self.time_budget);
+        let cost = if last_agent {
+            self.closed_valves(&from).map(|valve| self.valve_rate(valve) * (self.time_budget - self.start_time)).sum()
+        } else {
+            0
+        };
+        steps.push(step(AgentState { time: self.time_budget, ..agent_from }, from.open_valves, Action::Wait, cost));
+        steps
+    }
+
+    /// A lower bound on the relief still to be lost: each closed valve opened as soon as the
+    /// nearest agent could get to it. Valves left closed at the end have already been paid for
+    pub fn path_heuristic(&self, state: &State<N>) -> u32 {
+        if self.is_goal(state) { return 0 }
+        self.closed_valves(state).map(|valve| {
+            let room_id = self.valve_room_ids[valve as usize];
+            let earliest = state.agents.iter()
+                .filter(|agent| agent.time < self.time_budget)
+                .map(|agent| agent.time.saturating_add(self.travel_time(agent.room_id, room_id)).saturating_add(1))
+                .min()
+                .unwrap_or(self.time_budget)
+                .min(self.time_budget);
+            self.valve_rate(valve) * (earliest - self.start_time)
+        }).sum()
+    }
+
+    /// Who opens what along `path`, a sequence of states each one step from the last. Agents are
+    /// sorted within a state, so each is followed by where it was before it acted
+    pub fn plan(&self, path: &[State<N>]) -> Result<Plan> {
+        let mut agents = self.initial_state.agents;
+        let mut openings = vec![];
+        for (from, to) in path.iter().tuple_windows() {
+            let step = self.steps_from(*from).into_iter().find(|step| step.to == *to)
+                .ok_or_else(|| eyre!("There's no step from {:?} to {:?}", from, to))?;
+            let agent = agents.iter().position(|a| *a == step.agent_from)
+                .ok_or_else(|| eyre!("No agent is at {:?}", step.agent_from))?;
+            agents[agent] = step.agent_to;
+            if let Action::OpenValve { room_id } = step.action {
+                openings.push(Opening { minute: step.agent_to.time, agent, room_id });
+            }
+        }
+        Ok(Plan::new(N, self.initial_state.agents[0].room_id, self.time_budget, openings))
     }
 }
 
 mod graph_traits {
-    use std::ops::RangeInclusive;
-
-    use petgraph::adj::Neighbors;
-    use petgraph::prelude::*;
     use petgraph::visit::*;
     use super::*;
 
-    impl<'a> GraphBase for StateSpace<'a> {
-        type EdgeId = Step;
-        type NodeId = State;
+    impl<'a, const N: usize> GraphBase for StateSpace<'a, N> {
+        type EdgeId = Step<N>;
+        type NodeId = State<N>;
     }
 
-    impl<'a> GraphProp for StateSpace<'a> {
+    impl<'a, const N: usize> GraphProp for StateSpace<'a, N> {
         type EdgeType = Directed;
     }
 
-    impl<'a> Data for StateSpace<'a> {
+    impl<'a, const N: usize> Data for StateSpace<'a, N> {
         type NodeWeight = Self::NodeId;
         type EdgeWeight = Self::EdgeId;
     }
 
-    impl<'a> IntoNeighbors for &'a StateSpace<'a> {
-        type Neighbors = NeighborsFrom<'a>;
+    impl<'a, const N: usize> IntoNeighbors for &'a StateSpace<'a, N> {
+        type Neighbors = NeighborsFrom<N>;
 
         fn neighbors(self, a:Self::NodeId) -> Self::Neighbors {
             NeighborsFrom::new(self, a)
         }
     }
 
-    impl<'a> EdgeRef for Step {
-        type NodeId = State;
-        type EdgeId = Step;
-        type Weight = Step;
+    impl<const N: usize> EdgeRef for Step<N> {
+        type NodeId = State<N>;
+        type EdgeId = Step<N>;
+        type Weight = Step<N>;
 
-        fn source(&self) -> State {
+        fn source(&self) -> State<N> {
             self.from
         }
 
-        fn target(&self) -> State {
+        fn target(&self) -> State<N> {
             self.to
         }
 
-        fn weight(&self) -> &Step {
+        fn weight(&self) -> &Step<N> {
             self
         }
 
-        fn id(&self) -> Step {
+        fn id(&self) -> Step<N> {
             *self
         }
     }
 
-    impl<'a> IntoEdgeReferences for &'a StateSpace<'a> {
-        type EdgeRef = Step;
-        type EdgeReferences = AllEdges<'a>;
+    impl<'a, const N: usize> IntoEdgeReferences for &'a StateSpace<'a, N> {
+        type EdgeRef = Step<N>;
+        type EdgeReferences = AllEdges<'a, N>;
 
         fn edge_references(self) -> Self::EdgeReferences {
             AllEdges::new(self)
         }
     }
 
-    impl<'a> IntoEdges for &'a StateSpace<'a> {
-        type Edges = StepsFrom<'a>;
+    impl<const N: usize> NodeRef for State<N> {
+        type NodeId = State<N>;
+        type Weight = State<N>;
+
+        fn id(&self) -> Self::NodeId {
+            *self
+        }
+
+        fn weight(&self) -> &Self::Weight {
+            self
+        }
+    }
+
+    impl<'a, const N: usize> IntoNodeReferences for &'a StateSpace<'a, N> {
+        type NodeRef = State<N>;
+        type NodeReferences = AllNodes<'a, N>;
+
+        fn node_references(self) -> Self::NodeReferences {
+            AllNodes::new(self)
+        }
+    }
+
+    impl<'a, const N: usize> IntoNodeIdentifiers for &'a StateSpace<'a, N> {
+        type NodeIdentifiers = AllNodes<'a, N>;
+
+        fn node_identifiers(self) -> Self::NodeIdentifiers {
+            self.node_references()
+        }
+    }
+
+    impl<'a, const N: usize> IntoEdges for &'a StateSpace<'a, N> {
+        type Edges = StepsFrom<N>;
 
         fn edges(self, a: Self::NodeId) -> Self::Edges {
             StepsFrom::new(self, a)
         }
     }
 
-    impl<'a> Visitable for StateSpace<'a> {
-        type Map = HashSet<State>;
+    impl<'a, const N: usize> Visitable for StateSpace<'a, N> {
+        type Map = HashSet<State<N>>;
 
         fn visit_map(self: &Self) -> Self::Map {
             HashSet::new()
@@ -222,15 +309,15 @@ mod graph_traits {
         }
     }
 
-    pub struct AllEdges<'a> {
-        state_space: &'a StateSpace<'a>,
-        seen: HashSet<State>,
-        open: Vec<State>,
-        step_iter: StepsFrom<'a>
+    pub struct AllEdges<'a, const N: usize> {
+        state_space: &'a StateSpace<'a, N>,
+        seen: HashSet<State<N>>,
+        open: Vec<State<N>>,
+        step_iter: StepsFrom<N>
     }
 
-    impl<'a> AllEdges<'a> {
-        fn new(state_space: &'a StateSpace) -> Self {
+    impl<'a, const N: usize> AllEdges<'a, N> {
+        fn new(state_space: &'a StateSpace<'a, N>) -> Self {
             let seen = HashSet::from([state_space.initial_state]);
             let open = vec![];
             let step_iter = StepsFrom::new(state_space, state_space.initial_state);
@@ -238,109 +325,80 @@ mod graph_traits {
         }
     }
 
-    impl<'a> Iterator for AllEdges<'a> {
-        type Item = Step;
+    impl<'a, const N: usize> Iterator for AllEdges<'a, N> {
+        type Item = Step<N>;
 
         fn next(&mut self) -> Option<Self::Item> {
-            if let Some(step) = self.step_iter.next() {
-                if self.seen.insert(step.to) {
-                    self.open.push(step.to)
+            loop {
+                if let Some(step) = self.step_iter.next() {
+                    if self.seen.insert(step.to) {
+                        self.open.push(step.to)
+                    }
+                    return Some(step)
                 }
-                return Some(step)
-            } else if let Some(open_state) = self.open.pop() {
+                let open_state = self.open.pop()?;
                 self.step_iter = StepsFrom::new(self.state_space, open_state);
-                self.next()
-            } else {
-                None
             }
         }
     }
 
-    enum NeighborStage {
-        EmitOpen,
-        EmitMoves,
-        EmitWaits,
+    pub struct AllNodes<'a, const N: usize> {
+        state_space: &'a StateSpace<'a, N>,
+        seen: HashSet<State<N>>,
+        open: Vec<State<N>>,
     }
 
-    pub struct StepsFrom<'a> {
-        state_space: &'a StateSpace<'a>,
-        from: State,
-        stage: NeighborStage,
-        connected_rooms_iter: Box<dyn Iterator<Item = usize> + 'a>,
-        wait_ticks: RangeInclusive<u32>,
-    }
-
-    impl<'a> StepsFrom<'a> {
-        fn new(state_space: &'a StateSpace<'a>, from: State) -> Self {
-            let stage = NeighborStage::EmitMoves;
-            let open_valves = from.open_valves;
-            let connected_rooms_iter = Box::new(state_space.valve_room_ids.iter().copied().filter(move |vid| !open_valves.contains(*vid as u32)));
-            //let wait_ticks = u32::max(1, u32::saturating_sub(26, from.time()))..=(30 - from.time());
-            let wait_ticks = (30 - from.time())..=(30 - from.time());
-            Self { state_space, from, stage, connected_rooms_iter, wait_ticks }
+    impl<'a, const N: usize> AllNodes<'a, N> {
+        fn new(state_space: &'a StateSpace<'a, N>) -> Self {
+            let seen = HashSet::from([state_space.initial_state]);
+            let open = vec![state_space.initial_state];
+            Self { state_space, seen, open }
         }
     }
 
-    impl<'a> Iterator for StepsFrom<'a> {
-        type Item = Step;
+    impl<'a, const N: usize> Iterator for AllNodes<'a, N> {
+        type Item = State<N>;
 
         fn next(&mut self) -> Option<Self::Item> {
-            if self.from.time >= ERUPTION_TIME { return None }
-            match self.stage {
-                NeighborStage::EmitOpen => unreachable!(),
-                NeighborStage::EmitMoves => {
-                    if let Some(step) = self.connected_rooms_iter.next().map(|next_room_id| self.from.move_to_step(self.state_space, next_room_id)) {
-                        Some(step)
-                    } else {
-                        self.stage = NeighborStage::EmitWaits;
-                        self.next()
-                    }
-                },
-                NeighborStage::EmitWaits => {
-                    self.wait_ticks.next().map(|ticks| self.from.wait_step(ticks))
-                },
+            let node = self.open.pop()?;
+            for neighbor in self.state_space.neighbors(node) {
+                if self.seen.insert(neighbor) {
+                    self.open.push(neighbor)
+                }
             }
+            Some(node)
         }
     }
 
-    pub struct NeighborsFrom<'a>(StepsFrom<'a>);
-    
-    impl<'a> NeighborsFrom<'a> {
-        fn new(state_space: &'a StateSpace<'a>, from: State) -> Self {
-            Self(StepsFrom::new(state_space, from))
+    pub struct StepsFrom<const N: usize>(std::vec::IntoIter<Step<N>>);
+
+    impl<const N: usize> StepsFrom<N> {
+        fn new(state_space: &StateSpace<N>, from: State<N>) -> Self {
+            Self(state_space.steps_from(from).into_iter())
         }
     }
 
-    impl<'a> Iterator for NeighborsFrom<'a> {
-        type Item = State;
+    impl<const N: usize> Iterator for StepsFrom<N> {
+        type Item = Step<N>;
 
         fn next(&mut self) -> Option<Self::Item> {
-            self.0.next().map(|step| step.to)
+            self.0.next()
         }
     }
 
-}
-
-pub use graph_traits::*;
+    pub struct NeighborsFrom<const N: usize>(StepsFrom<N>);
 
-impl State {
-    pub fn pressure_buildup(&self, caverns: &Caverns) -> u32 {
-        let cost = caverns.rooms().enumerate().map(|(id, room)| {
-            if self.open_valves.contains(id as u32) {
-                0
-            } else {
-                room.valve_rate()
-            }
-        }).sum();
-        cost
+    impl<const N: usize> NeighborsFrom<N> {
+        fn new(state_space: &StateSpace<N>, from: State<N>) -> Self {
+            Self(StepsFrom::new(state_space, from))
+        }
     }
 
-    pub fn path_heuristic(&self, caverns: &Caverns) -> u32 {
-        // cannot over-estimate remaining cost
-        let mut closed_valves = caverns.rooms().enumerate().filter(|(id, room)| !self.open_valves.contains(*id as u32)).map(|(_id, room)| room.valve_rate()).collect_vec();
-        closed_valves.sort();
-        let cost = (1..(30 - self.time())).zip(closed_valves.iter().rev()).map(|(ticks, rate)| rate*ticks).sum();
-        //dbg!(self, cost);
-        cost
+    impl<const N: usize> Iterator for NeighborsFrom<N> {
+        type Item = State<N>;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            self.0.next().map(|step| step.to)
+        }
     }
 }
@@ -0,0 +1,166 @@
+use aoc2022::bitset::BitSet;
+use aoc2022::prelude::*;
+use crate::caverns::*;
+use crate::plan::*;
+
+/// Solves day 16 by finding the best relief one agent can get from every set of valves, then
+/// sharing disjoint sets out between the agents
+pub struct SubsetSolver<'a> {
+    caverns: &'a Caverns,
+    valve_room_ids: Vec<usize>,
+    /// Minutes to walk between valve rooms, by position in `valve_room_ids`
+    travel_times: Vec<Vec<u32>>,
+    /// Minutes to walk from the start room to each valve room
+    start_travel_times: Vec<u32>,
+    start_room: usize,
+    /// Minute the agents set off
+    start_time: u32,
+    /// Minutes each agent has to work in
+    minutes: u32,
+}
+
+impl<'a> SubsetSolver<'a> {
+    /// The table has an entry for each of the 2^V subsets of V valves, and sharing them out
+    /// between agents visits every subset of each subset, 3^V in all, per extra agent. At 16
+    /// valves that's about 43 million steps; each valve more triples it
+    const MAX_VALVES: usize = 16;
+
+    /// Agents set off from `start_room` at `start_time`, and the volcano erupts after
+    /// `time_budget` minutes
+    pub fn new(caverns: &'a Caverns, start_room: &str, time_budget: u32, start_time: u32) -> Result<Self> {
+        let start_room = caverns.room_id(start_room).ok_or_else(|| eyre!("There's no room named {}", start_room))?;
+        let start = caverns.room(start_room);
+        let minutes = time_budget.checked_sub(start_time)
+            .ok_or_else(|| eyre!("Starting at minute {} leaves no time before minute {}", start_time, time_budget))?;
+        let valve_room_ids: Vec<usize> = caverns.rooms().enumerate().filter_map(|(id, r)| if r.valve_rate() > 0 { Some(id) } else { None }).collect();
+        if valve_room_ids.len() > Self::MAX_VALVES {
+            bail!("The subset solver handles at most {} valves, not {}; try --solver astar", Self::MAX_VALVES, valve_room_ids.len());
+        }
+
+        let cavern_graph = caverns.build_graph();
+        let path_lengths = cavern_graph.shortest_paths();
+        let travel_time = |from: &Room, to: usize| path_lengths[&(cavern_graph.room_index(from), cavern_graph.room_index(caverns.room(to)))];
+        let travel_times = valve_room_ids.iter()
+            .map(|from| valve_room_ids.iter().map(|to| travel_time(caverns.room(*from), *to)).collect())
+            .collect();
+        let start_travel_times = valve_room_ids.iter().map(|to| travel_time(start, *to)).collect();
+
+        Ok(Self { caverns, valve_room_ids, travel_times, start_travel_times, start_room, start_time, minutes })
+    }
+
+    fn valve_rate(&self, valve: usize) -> u32 {
+        self.caverns.room(self.valve_room_ids[valve]).valve_rate()
+    }
+
+    /// Minutes to walk from `from` (or the start room) to `to` and open it, if it can be reached
+    fn open_time(&self, from: Option<usize>, to: usize) -> Option<u32> {
+        match from {
+            Some(from) => self.travel_times[from][to],
+            None => self.start_travel_times[to]
+        }.checked_add(1)
+    }
+
+    /// Open `valve` with `minutes_left` after walking there and opening it, then try every valve
+    /// still closed from there
+    fn visit(&self, valve: usize, minutes_left: u32, opened: u32, relief: u32, best: &mut [u32]) {
+        let relief = relief + self.valve_rate(valve) * minutes_left;
+        best[opened as usize] = best[opened as usize].max(relief);
+        for next in 0..self.valve_room_ids.len() {
+            let Some(cost) = self.open_time(Some(valve), next) else { continue };
+            if !opened.contains(next as u32) && cost < minutes_left {
+                let mut opened = opened;
+                opened.insert(next as u32);
+                self.visit(next, minutes_left - cost, opened, relief, best);
+            }
+        }
+    }
+
+    /// The most relief one agent can get by opening only valves in each subset
+    fn best_by_subset(&self) -> Vec<u32> {
+        let valves = self.valve_room_ids.len();
+        let mut best = vec![0; 1 << valves];
+        for valve in 0..valves {
+            let Some(cost) = self.open_time(None, valve) else { continue };
+            if cost < self.minutes {
+                self.visit(valve, self.minutes - cost, u32::singleton(valve as u32), 0, &mut best);
+            }
+        }
+
+        // a subset does at least as well as any set inside it
+        for valve in 0..valves as u32 {
+            for opened in 0..best.len() as u32 {
+                if opened.contains(valve) {
+                    let mut without = opened;
+                    without.remove(valve);
+                    best[opened as usize] = best[opened as usize].max(best[without as usize]);
+                }
+            }
+        }
+        best
+    }
+
+    /// The best order to open valves in `within` from `valve` (or the start room) with
+    /// `minutes_left`, as the relief and each valve with the minutes left once it's open
+    fn best_order(&self, valve: Option<usize>, minutes_left: u32, within: u32) -> (u32, Vec<(usize, u32)>) {
+        let mut best = (0, vec![]);
+        for next in 0..self.valve_room_ids.len() {
+            let Some(cost) = self.open_time(valve, next) else { continue };
+            if within.contains(next as u32) && cost < minutes_left {
+                let mut rest = within;
+                rest.remove(next as u32);
+                let (relief, mut order) = self.best_order(Some(next), minutes_left - cost, rest);
+                let relief = relief + self.valve_rate(next) * (minutes_left - cost);
+                if relief > best.0 {
+                    order.insert(0, (next, minutes_left - cost));
+                    best = (relief, order);
+                }
+            }
+        }
+        best
+    }
+
+    /// The valves each of `agents` agents should open to get the most relief working together
+    fn share_out(&self, agents: usize) -> Vec<u32> {
+        let alone = self.best_by_subset();
+        let mut together = alone.clone();
+        // for each number of agents past the first, the valves left to the others in the best split
+        let mut splits = vec![];
+        for _ in 1..agents {
+            let (best, theirs): (Vec<u32>, Vec<u32>) = (0..alone.len() as u32).map(|opened| {
+                // every way of splitting `opened` between the new agent and the others
+                let mut theirs = opened;
+                let mut best = (0, 0);
+                loop {
+                    let relief = together[theirs as usize] + alone[opened.difference(&theirs) as usize];
+                    best = best.max((relief, theirs));
+                    if theirs == 0 { break best }
+                    theirs = (theirs - 1) & opened;
+                }
+            }).unzip();
+            together = best;
+            splits.push(theirs);
+        }
+
+        let mut opened = (alone.len() - 1) as u32;
+        let mut subsets = vec![];
+        for theirs in splits.iter().rev() {
+            subsets.push(opened.difference(&theirs[opened as usize]));
+            opened = theirs[opened as usize];
+        }
+        subsets.push(opened);
+        subsets.reverse();
+        subsets
+    }
+
+    /// Who opens what to get the most relief with `agents` agents
+    pub fn plan(&self, agents: usize) -> Plan {
+        let openings = self.share_out(agents).into_iter().enumerate().flat_map(|(agent, subset)| {
+            self.best_order(None, self.minutes, subset).1.into_iter().map(move |(valve, minutes_left)| Opening {
+                minute: self.start_time + self.minutes - minutes_left,
+                agent,
+                room_id: self.valve_room_ids[valve],
+            })
+        }).collect();
+        Plan::new(agents, self.start_room, self.start_time + self.minutes, openings)
+    }
+}
@@ -1,70 +1,68 @@
-use std::{ops::{Index, IndexMut}, borrow::Borrow, convert::TryFrom, str::FromStr, sync::atomic::{AtomicU32, Ordering}};
+use std::{borrow::Borrow, convert::TryFrom};
 use itertools::Itertools;
 
 use aoc2022::prelude::*;
+use aoc2022::cycle::CycleDetector;
+use aoc2022::render::Color;
+use aoc2022::visualise::{Canvas, Simulation, VisualiseCLI};
 
 #[derive(Parser)]
 struct Cli {
     #[command(flatten)]
     input: InputCLI<17>,
+    #[command(flatten)]
+    rules: RulesCLI,
+    #[command(flatten)]
+    visualise: VisualiseCLI,
+    /// Rock count at which the visualiser stops
+    #[arg(long, default_value_t = 2022)]
+    stop_after: usize,
+    /// Rock counts to report the tower height after
+    #[arg(long, value_delimiter = ',', default_values_t = [2022, 1_000_000_000_000])]
+    rocks: Vec<u64>,
+    /// Drop every rock instead of skipping ahead once the state repeats
     #[arg(long)]
-    gui: bool
+    brute_force: bool
 }
 
-#[derive(PartialEq, Eq, Clone, Copy, Debug)]
-enum Cell {
-    Empty,
-    Full
-}
-
-use Cell::*;
-
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-struct Line([Cell; Line::WIDTH]);
+/// One line of the well with bit `n` set when column `n` is full
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+struct Line(u64);
 
 impl Line {
-    const WIDTH: usize = 7;
-    const FULL: Line = Line([Full; Line::WIDTH]);
-    const CLEAR: Line = Line([Empty; Line::WIDTH]);
+    const MAX_WIDTH: usize = u64::BITS as usize;
+    const CLEAR: Line = Line(0);
 
-    fn is_clear(&self) -> bool {
-        self.0.iter().all(|x| *x == Empty)
+    fn full(width: usize) -> Line {
+        Line(u64::MAX >> (Self::MAX_WIDTH - width))
     }
 
-    fn is_full(&self) -> bool {
-        self.0.iter().all(|x| *x == Full)
+    fn is_clear(&self) -> bool {
+        self.0 == 0
     }
-}
 
-impl Default for Line {
-    fn default() -> Self {
-        Self([Empty; 7])
+    fn is_filled(&self, column: usize) -> bool {
+        self.0 & (1 << column) != 0
     }
-}
-
-impl Index<usize> for Line {
-    type Output = Cell;
 
-    fn index(&self, index: usize) -> &Self::Output {
-        &self.0[index]
-    }
-}
-
-impl IndexMut<usize> for Line {
-    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
-        &mut self.0[index]
+    fn overlaps(&self, mask: u64) -> bool {
+        self.0 & mask != 0
     }
 }
 
 struct Well {
+    width: usize,
     lines: Vec<Line>,
     floor_offset: usize,
+    /// How many lines below the top of the tower are kept. Anything deeper reads as full, even
+    /// down a shaft a rock could still fall into, so a column that never fills can't keep the
+    /// whole tower alive
+    depth: usize,
 }
 
-impl Default for Well {
-    fn default() -> Self {
-        let lines = vec![Line::default(); 3];
-        Self { lines, floor_offset: 0 }
+impl Well {
+    fn new(width: usize, depth: usize) -> Self {
+        Self { width, lines: vec![Line::CLEAR; 3], floor_offset: 0, depth }
     }
 }
 
@@ -74,10 +72,6 @@ enum NoPhysicalLine {
 }
 
 impl Well {
-    fn add_line(&mut self, line: Line) {
-        self.lines.push(line)
-    }
-
     fn ensure_height(&mut self, height: usize) {
         if self.lines.len() + self.floor_offset < height {
             self.lines.resize(height - self.floor_offset, Line::CLEAR);
@@ -97,11 +91,11 @@ impl Well {
         }
     }
 
-    fn line(&self, line_num: usize) -> &Line {
+    fn line(&self, line_num: usize) -> Line {
         match self.phys_line(line_num) {
-            Ok(phys_line) => &self.lines[phys_line],
-            Err(NoPhysicalLine::AboveLines) => &Line::CLEAR,
-            Err(NoPhysicalLine::BelowVirtualFloor) => &Line::FULL,
+            Ok(phys_line) => self.lines[phys_line],
+            Err(NoPhysicalLine::AboveLines) => Line::CLEAR,
+            Err(NoPhysicalLine::BelowVirtualFloor) => Line::full(self.width),
         }
     }
 
@@ -114,27 +108,10 @@ impl Well {
     }
 }
 
-impl Index<&Coord> for Well {
-    type Output = Cell;
-
-    fn index(&self, index: &Coord) -> &Self::Output {
-        &self.line(index.line)[index.column]
-    }
-}
-
-impl IndexMut<&Coord> for Well {
-    fn index_mut(&mut self, index: &Coord) -> &mut Self::Output {
-        &mut self.line_mut(index.line).unwrap()[index.column]
-    }
-}
-
 impl Well {
-    fn fill<C: Borrow<Coord>>(&mut self, coord: C) {
-        self[coord.borrow()] = Full
-    }
-
     fn is_filled<C: Borrow<Coord>>(&self, coord: C) -> bool {
-        self[coord.borrow()] == Full
+        let coord = coord.borrow();
+        self.line(coord.line).is_filled(coord.column)
     }
 
     fn highest_occupied_line(&self) -> Option<usize> {
@@ -145,79 +122,125 @@ impl Well {
             Some(self.lines.len() - clear_at_top + self.floor_offset)
         }
     }
-}
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-enum Shape {
-    HorizLine,
-    Cross,
-    Ell,
-    VertLine,
-    Square,
+    /// The lowest line that a falling rock could still reach, but no more than `depth` below the
+    /// top. Rocks only move down and sideways, so this floods the empty space downwards from the
+    /// clear line above the tower
+    fn lowest_reachable_line(&self) -> usize {
+        let top = match self.highest_occupied_line() {
+            Some(top) => top,
+            None => return self.floor_offset
+        };
+        let bottom = self.floor_offset.max(top.saturating_sub(self.depth));
+        let full = Line::full(self.width).0;
+        let mut reach = full;
+        for line in (bottom..top).rev() {
+            let free = !self.line(line).0 & full;
+            reach &= free;
+            loop {
+                let spread = (reach | reach << 1 | reach >> 1) & free;
+                if spread == reach { break }
+                reach = spread;
+            }
+            if reach == 0 {
+                return line + 1;
+            }
+        }
+        bottom
+    }
+
+    /// Drop the lines no rock can reach any more. They read as full below the new floor
+    fn trim(&mut self) {
+        let new_floor = self.lowest_reachable_line();
+        if new_floor > self.floor_offset {
+            self.lines.drain(0..(new_floor - self.floor_offset));
+            self.floor_offset = new_floor;
+        }
+    }
+
+    /// For each column, how far below the top of the tower its highest filled cell is. Columns
+    /// with nothing in them reach down to the floor, which trimming keeps within `depth`
+    fn surface_profile(&self) -> Vec<usize> {
+        let height = self.highest_occupied_line().unwrap_or(0);
+        (0..self.width).map(|column| {
+            (self.floor_offset..height).rev()
+                .position(|line| self.is_filled(Coord { line, column }))
+                .unwrap_or(height - self.floor_offset)
+        }).collect()
+    }
 }
 
-use Shape::*;
+/// A rock as one bitmask per line, bottom line first, with bit 0 at the shape's left edge
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Shape {
+    rows: Vec<u64>,
+    width: usize
+}
 
 impl Shape {
-    fn sequence() -> impl Iterator<Item = Shape> {
-        [HorizLine, Cross, Ell, VertLine, Square].into_iter().cycle()
-    }
-
-    fn width(self) -> usize {
-        match self {
-            HorizLine => 4,
-            Cross => 3,
-            Ell => 3,
-            VertLine => 1,
-            Square => 2
-        }
-    }
-
-    fn height(self) -> usize {
-        match self {
-            HorizLine => 1,
-            Cross => 3,
-            Ell => 3,
-            VertLine => 4,
-            Square => 2,
-        }
-    }
-
-    fn occupied_coords(self) -> Vec<Coord> {
-        match self {
-            HorizLine => vec![
-                Coord { line: 0, column: 0 },
-                Coord { line: 0, column: 1 },
-                Coord { line: 0, column: 2 },
-                Coord { line: 0, column: 3 },
-            ],
-            Cross => vec![
-                Coord { line: 0, column: 1 },
-                Coord { line: 1, column: 0 },
-                Coord { line: 1, column: 1 },
-                Coord { line: 1, column: 2 },
-                Coord { line: 2, column: 1 },
-            ],
-            Ell => vec![
-                Coord { line: 0, column: 0 },
-                Coord { line: 0, column: 1 },
-                Coord { line: 0, column: 2 },
-                Coord { line: 1, column: 2 },
-                Coord { line: 2, column: 2 },
-            ],
-            VertLine => vec![
-                Coord { line: 0, column: 0 },
-                Coord { line: 1, column: 0 },
-                Coord { line: 2, column: 0 },
-                Coord { line: 3, column: 0 },
-            ],
-            Square => vec![
-                Coord { line: 0, column: 0 },
-                Coord { line: 0, column: 1 },
-                Coord { line: 1, column: 0 },
-                Coord { line: 1, column: 1 },
-            ],
+    /// The puzzle's rocks, in the order they fall
+    const DEFAULT_SHAPES: &'static str = "\
+####
+
+.#.
+###
+.#.
+
+..#
+..#
+###
+
+#
+#
+#
+#
+
+##
+##
+";
+
+    /// Parse ASCII art with `#` for rock and `.` for space, top line first. Empty lines and
+    /// columns around the rock are trimmed, but empty lines within it are kept
+    fn from_art<S: AsRef<str>>(art: &[S]) -> Result<Shape> {
+        let mut rows = art.iter().rev().map(|line| {
+            line.as_ref().trim_end().bytes().enumerate().try_fold(0u64, |mask, (column, b)| match b {
+                b'#' if column < Line::MAX_WIDTH => Ok(mask | 1 << column),
+                b'#' => bail!("Shapes can be at most {} wide", Line::MAX_WIDTH),
+                b'.' => Ok(mask),
+                _ => bail!("Unexpected {:?} in shape, expected '#' or '.'", b as char)
+            })
+        }).collect::<Result<Vec<_>>>()?;
+        let left = rows.iter().map(|row| row.trailing_zeros()).min().unwrap_or(u64::BITS);
+        if left == u64::BITS {
+            bail!("Shape has no rock in it");
+        }
+        // rows are bottom first, and there's at least one with rock in it
+        let bottom = rows.iter().position(|row| *row != 0).unwrap();
+        let top = rows.iter().rposition(|row| *row != 0).unwrap();
+        rows.truncate(top + 1);
+        rows.drain(..bottom);
+        rows.iter_mut().for_each(|row| *row >>= left);
+        let width = rows.iter().map(|row| (u64::BITS - row.leading_zeros()) as usize).max().unwrap();
+        Ok(Shape { rows, width })
+    }
+
+    /// Shapes separated by blank lines
+    fn parse_all(text: &str) -> Result<Vec<Shape>> {
+        let shapes: Vec<Shape> = text.lines().paragraphs().map(|art| Shape::from_art(&art)).try_collect()?;
+        if shapes.is_empty() {
+            bail!("No shapes defined");
         }
+        Ok(shapes)
+    }
+
+    fn height(&self) -> usize {
+        self.rows.len()
+    }
+
+    fn occupied_coords(&self) -> Vec<Coord> {
+        self.rows.iter().enumerate().flat_map(|(line, row)| {
+            (0..self.width).filter(move |column| row & (1 << column) != 0).map(move |column| Coord { line, column })
+        }).collect()
     }
 }
 
@@ -312,7 +335,6 @@ enum Direction {
 }
 
 use Direction::*;
-use speedy2d::{color::Color, shape::Rectangle};
 
 impl TryFrom<u8> for Direction {
     type Error = Report;
@@ -334,10 +356,7 @@ impl Coord {
                 if self.column == 0 { None }
                 else { Some(Self { column: self.column - 1, line: self.line }) }
             },
-            Right => {
-                if self.column >= Line::WIDTH - 1 { None }
-                else { Some(Self { column: self.column + 1, line: self.line }) }
-            },
+            Right => Some(Self { column: self.column + 1, line: self.line }),
             Down => {
                 if self.line == 0 { None }
                 else { Some(Self { column: self.column, line: self.line - 1 }) }
@@ -355,11 +374,8 @@ impl Coord {
                 }
             },
             Right => {
-                if self.column >= Line::WIDTH-1 { false }
-                else { 
-                    self.column += 1;
-                    true
-                }
+                self.column += 1;
+                true
             },
             Down => {
                 if self.line == 0 { false }
@@ -373,13 +389,6 @@ impl Coord {
 }
 
 impl<T> Positioned<T> {
-    fn moved(&self, direction: Direction) -> Option<Self>
-        where T: Clone
-    {
-        self.position.moved(direction).map(|position|
-            Self { position, item: self.item.clone() })
-    }
-
     fn moove(&mut self, direction: Direction) -> bool {
         self.position.moove(direction)
     }
@@ -391,8 +400,21 @@ impl Positioned<Shape> {
     }
 
     fn can_move(&self, direction: Direction, well: &Well) -> bool {
-        self.occupied_coords().into_iter().map(|coord| coord.moved(direction))
-            .all(|coord| coord.map(|coord| !well.is_filled(coord)).unwrap_or(false))
+        match self.position.moved(direction) {
+            Some(position) if position.column + self.item.width <= well.width => {
+                let moved = Positioned { position, item: &self.item };
+                let fits = moved.masks().all(|(line, mask)| !well.line(line).overlaps(mask));
+                fits
+            },
+            _ => false
+        }
+    }
+}
+
+impl<S: Borrow<Shape>> Positioned<S> {
+    /// The rock's bitmask for each line it covers, bottom line first
+    fn masks(&self) -> impl Iterator<Item = (usize, u64)> + '_ {
+        self.item.borrow().rows.iter().enumerate().map(|(i, row)| (self.position.line + i, row << self.position.column))
     }
 }
 
@@ -405,293 +427,335 @@ impl Shape {
 impl Well {
     fn add(&mut self, rock: &Positioned<Shape>) {
         self.ensure_height(rock.position.line + rock.item.height());
-        for coord in rock.occupied_coords() {
-            debug_assert!(!self.is_filled(coord));
-            self.fill(coord)
-        }
-        /*
-        if let Some(new_floor) = rock.occupied_coords().into_iter().map(|c| c.line).filter(|l| self.line(*l).is_full()).min() {
-            assert!(new_floor >= self.floor_offset);
-            //dbg!(new_floor);
-            self.lines.drain(0..(new_floor - self.floor_offset));
-            self.floor_offset = new_floor;
+        for (line, mask) in rock.masks() {
+            let line = self.line_mut(line).unwrap();
+            debug_assert!(!line.overlaps(mask));
+            line.0 |= mask;
         }
-        */
+        self.trim();
     }
 }
 
-struct Main {
-    simulator: Simulator,
-    cell_size: u32,
-    tick_rate: std::sync::Arc<AtomicU32>,
-    win_size: speedy2d::dimen::UVec2,
+/// How rocks enter the well
+struct Rules {
+    width: usize,
+    shapes: Vec<Shape>,
+    /// Empty lines between the top of the tower and a new rock
+    spawn_gap: usize,
+    /// Empty columns between the left wall and a new rock
+    spawn_column: usize,
 }
 
-impl Main {
-    fn new(jets: Vec<Direction>) -> Self {
-        Self {
-            simulator: Simulator::new(jets),
-            cell_size: 16,
-            tick_rate: std::sync::Arc::new(1000.into()),
-            win_size: speedy2d::dimen::UVec2::ZERO,
+#[derive(clap::Args)]
+struct RulesCLI {
+    /// File of ASCII-art rock shapes separated by blank lines, `#` for rock and `.` for space
+    #[arg(long)]
+    shapes: Option<std::path::PathBuf>,
+    #[arg(long, default_value_t = 7)]
+    well_width: usize,
+    /// Empty lines left between the top of the tower and each new rock
+    #[arg(long, default_value_t = 3)]
+    spawn_gap: usize,
+    /// Empty columns left between the left wall and each new rock
+    #[arg(long, default_value_t = 2)]
+    spawn_column: usize,
+}
+
+impl RulesCLI {
+    fn rules(&self) -> Result<Rules> {
+        let shapes = match &self.shapes {
+            Some(path) => Shape::parse_all(&std::fs::read_to_string(path)?)?,
+            None => Shape::parse_all(Shape::DEFAULT_SHAPES)?
+        };
+        Rules::new(self.well_width, shapes, self.spawn_gap, self.spawn_column)
+    }
+}
+
+impl Rules {
+    fn new(width: usize, shapes: Vec<Shape>, spawn_gap: usize, spawn_column: usize) -> Result<Rules> {
+        if !(1..=Line::MAX_WIDTH).contains(&width) {
+            bail!("The well must be between 1 and {} wide", Line::MAX_WIDTH);
         }
-    }
-
-    fn fill_cell(&self, coord: &Coord, color: Color, graphics: &mut speedy2d::Graphics2D) {
-        let well_width = self.cell_size * (Line::WIDTH as u32 + 2);
-        let well_x = ((self.win_size.x - well_width) / 2) as f32;
-        let well_width = well_width as f32;
-        let well_height = self.win_size.y as f32 - 8.0;
-
-        graphics.draw_rectangle(
-            Rectangle::from_tuples(
-                (well_x + (self.cell_size * (coord.column as u32 + 1)) as f32 + 2.5, well_height + 4.5 - (self.cell_size * (coord.line as u32 + 1)) as f32),
-                (well_x + (self.cell_size * (coord.column as u32 + 2)) as f32 - 2.5, well_height + 4.5 - (self.cell_size * (coord.line as u32 + 2)) as f32 - 2.0)), color);
-    }
-}
-
-impl speedy2d::window::WindowHandler for Main {
-    fn on_start(&mut self, helper: &mut speedy2d::window::WindowHelper<()>, info: speedy2d::window::WindowStartupInfo) {
-        self.win_size = *info.viewport_size_pixels();
-
-        let eventer = helper.create_user_event_sender();
-        let tick_rate = self.tick_rate.clone();
-        std::thread::spawn(move || {
-            loop {
-                std::thread::sleep(std::time::Duration::from_secs(1) / tick_rate.load(Ordering::Relaxed));
-                eventer.send_event(()).unwrap()
-            }
-        });
-    }
-
-    fn on_resize(&mut self, helper: &mut speedy2d::window::WindowHelper<()>, size_pixels: speedy2d::dimen::UVec2) {
-        self.win_size = size_pixels;
-        helper.request_redraw();
-    }
-
-    fn on_user_event(&mut self, helper: &mut speedy2d::window::WindowHelper<()>, _user_event: ()) {
-        self.simulator.step();
-        if self.simulator.falling_rock.is_none() && self.simulator.rock_count == 2022 {
-            println!("After 2022 rocks, the tower height is {}", self.simulator.well.highest_occupied_line().unwrap());
-            helper.terminate_loop()
-        } else {
-            helper.request_redraw();
+        if let Some(shape) = shapes.iter().find(|shape| spawn_column + shape.width > width) {
+            bail!("A shape {} wide spawning at column {} doesn't fit in a well {} wide", shape.width, spawn_column, width);
         }
+        Ok(Rules { width, shapes, spawn_gap, spawn_column })
     }
 
-    fn on_draw(&mut self, _helper: &mut speedy2d::window::WindowHelper<()>, graphics: &mut speedy2d::Graphics2D) {
-        let well_width = self.cell_size * (Line::WIDTH as u32 + 2);
-        let well_x = ((self.win_size.x - well_width) / 2) as f32;
-        let well_width = well_width as f32;
-        let well_height = self.win_size.y as f32 - 8.0;
-
-        graphics.clear_screen(Color::WHITE);
+    /// How deep below the top of the tower the well is kept, in heights of the tallest shape
+    const KEPT_SHAPE_HEIGHTS: usize = 32;
 
-        graphics.draw_line((well_x + 0.5, 4.5), (well_x + 0.5, well_height + 4.5), 1.0, Color::BLACK);
-        graphics.draw_line((well_x + well_width + 0.5, 4.5), (well_x + well_width + 0.5, well_height + 4.5), 1.0, Color::BLACK);
-        graphics.draw_line((well_x + 0.5, well_height + 4.5), (well_x + well_width + 0.5, well_height + 4.5), 1.0, Color::BLACK);
-
-        for (line_num, line) in self.simulator.well.lines.iter().enumerate() {
-            for (col_num, cell) in line.0.iter().enumerate() {
-                if *cell == Full {
-                    self.fill_cell(&Coord { line: line_num, column: col_num }, Color::DARK_GRAY, graphics);
-                }
-            }
-        }
-
-        self.simulator.falling_rock.iter().for_each(|(_, rock)| {
-            rock.occupied_coords().into_iter().for_each(|coord| self.fill_cell(&coord, Color::LIGHT_GRAY, graphics));
-        })
+    fn well_depth(&self) -> usize {
+        self.shapes.iter().map(Shape::height).max().unwrap_or(1) * Self::KEPT_SHAPE_HEIGHTS
     }
-
 }
 
 struct Simulator {
+    rules: Rules,
     well: Well,
-    jets: Box<dyn Iterator<Item = Direction>>,
-    shapes: Box<dyn Iterator<Item = Shape>>,
-    falling_rock: Option<(usize, Positioned<Shape>)>,
+    jets: Vec<Direction>,
+    jet_index: usize,
+    shape_index: usize,
+    falling_rock: Option<Positioned<Shape>>,
     rock_count: usize,
 }
 
+/// Everything that decides how the tower grows from here on, so the growth repeats once a key does
+#[derive(PartialEq, Eq, Hash, Debug)]
+struct StateKey {
+    jet_index: usize,
+    shape_index: usize,
+    surface: Vec<usize>
+}
+
 impl Simulator {
-    fn new(jets: Vec<Direction>) -> Simulator {
+    /// How many lines of the well the visualiser shows
+    const VIEW_LINES: usize = 48;
+
+    fn new(rules: Rules, jets: Vec<Direction>) -> Simulator {
         Self {
-            jets: std::boxed::Box::new(jets.into_iter().cycle()),
-            well: Well::default(),
-            shapes: std::boxed::Box::new(Shape::sequence()),
+            well: Well::new(rules.width, rules.well_depth()),
+            rules,
+            jets,
+            jet_index: 0,
+            shape_index: 0,
             falling_rock: None,
             rock_count: 0
         }
     }
 
     fn step(&mut self) {
-        let (jet_pos, rock) = self.falling_rock.get_or_insert_with(|| {
+        let rock = self.falling_rock.get_or_insert_with(|| {
             let starting_position = Coord {
-                line: self.well.highest_occupied_line().unwrap_or(0) + 3,
-                column: 2
+                line: self.well.highest_occupied_line().unwrap_or(0) + self.rules.spawn_gap,
+                column: self.rules.spawn_column
             };
+            let shape = self.rules.shapes[self.shape_index].clone();
+            self.shape_index = (self.shape_index + 1) % self.rules.shapes.len();
             self.rock_count += 1;
-            (0, self.shapes.next().unwrap().at(starting_position))
+            shape.at(starting_position)
         });
-        let jet_dir = self.jets.next().unwrap();
+        let jet_dir = self.jets[self.jet_index];
+        self.jet_index = (self.jet_index + 1) % self.jets.len();
         if rock.can_move(jet_dir, &self.well) {
             rock.moove(jet_dir);
         }
         if rock.can_move(Direction::Down, &self.well) {
             rock.moove(Direction::Down);
         } else {
-            self.well.add(&rock);
+            self.well.add(rock);
             self.falling_rock = None;
         }
     }
+
+    /// Step until the next rock has come to rest
+    fn drop_rock(&mut self) {
+        self.step();
+        while self.falling_rock.is_some() {
+            self.step();
+        }
+    }
+
+    fn tower_height(&self) -> usize {
+        self.well.highest_occupied_line().unwrap_or(0)
+    }
+
+    fn state_key(&self) -> StateKey {
+        StateKey {
+            jet_index: self.jet_index,
+            shape_index: self.shape_index,
+            surface: self.well.surface_profile()
+        }
+    }
+}
+
+impl Simulation for Simulator {
+    fn step(&mut self) {
+        Simulator::step(self)
+    }
+
+    fn dimensions(&self) -> (usize, usize) {
+        (self.rules.width + 2, Self::VIEW_LINES + 1)
+    }
+
+    /// The top `VIEW_LINES` lines of the well, following the falling rock as it spawns
+    fn draw(&self, canvas: &mut dyn Canvas) {
+        let top = self.falling_rock.as_ref()
+            .map(|rock| rock.position.line + rock.item.height())
+            .unwrap_or(0)
+            .max(self.tower_height() + 1)
+            .max(Self::VIEW_LINES);
+        let bottom = top - Self::VIEW_LINES;
+        let row = |line: usize| top - 1 - line;
+
+        for r in 0..Self::VIEW_LINES {
+            canvas.fill_cell(0, r, Color::BLACK);
+            canvas.fill_cell(self.rules.width + 1, r, Color::BLACK);
+        }
+        if bottom == 0 {
+            (0..self.rules.width + 2).for_each(|column| canvas.fill_cell(column, Self::VIEW_LINES, Color::BLACK));
+        }
+        for line in bottom..top {
+            for column in 0..self.rules.width {
+                if self.well.is_filled(Coord { line, column }) {
+                    canvas.fill_cell(column + 1, row(line), Color::GREY);
+                }
+            }
+        }
+        if let Some(rock) = &self.falling_rock {
+            for coord in rock.occupied_coords().into_iter().filter(|c| (bottom..top).contains(&c.line)) {
+                canvas.fill_cell(coord.column + 1, row(coord.line), Color::YELLOW);
+            }
+        }
+    }
+
+    fn report(&self) -> String {
+        format!("After {} rocks, the tower height is {}", self.rock_count, self.tower_height())
+    }
+}
+
+/// The height of the tower after each of `rock_counts` rocks. Drops rocks only until the
+/// simulator state repeats, then extrapolates
+fn tower_heights(rules: Rules, jets: Vec<Direction>, rock_counts: &[u64]) -> Vec<u64> {
+    let mut sim = Simulator::new(rules, jets);
+    let mut heights = vec![0];
+    let mut detector = CycleDetector::new();
+    let most_rocks = rock_counts.iter().copied().max().unwrap_or(0) as usize;
+    let cycle = loop {
+        if let Some(cycle) = detector.observe(sim.state_key()) {
+            break Some(cycle);
+        }
+        if sim.rock_count >= most_rocks {
+            break None;
+        }
+        sim.drop_rock();
+        heights.push(sim.tower_height() as u64);
+    };
+
+    rock_counts.iter().map(|&rocks| {
+        let rocks = rocks as usize;
+        match cycle {
+            Some(cycle) if rocks >= heights.len() => cycle.extrapolate(rocks, |i| heights[i]),
+            _ => heights[rocks]
+        }
+    }).collect()
+}
+
+/// The height of the tower after each of `rock_counts` rocks, dropping every one of them
+fn simulated_tower_heights(rules: Rules, jets: Vec<Direction>, rock_counts: &[u64]) -> Vec<u64> {
+    let mut sim = Simulator::new(rules, jets);
+    let mut heights = vec![0; rock_counts.len()];
+    for (i, rocks) in rock_counts.iter().enumerate().sorted_by_key(|(_, rocks)| **rocks) {
+        while (sim.rock_count as u64) < *rocks {
+            sim.drop_rock();
+        }
+        heights[i] = sim.tower_height() as u64;
+    }
+    heights
 }
 
-#[derive(Debug)]
-struct Recurrence {
-    first_observed_rock_count: usize,
-    first_observed_height: usize,
-    periods: usize
+/// The jet pattern: the input up to the first whitespace
+fn parse_jets(input: &str) -> Result<Vec<Direction>> {
+    let jets: Vec<Direction> = input.bytes().take_while(|b| !b.is_ascii_whitespace()).map(Direction::try_from).try_collect()?;
+    if jets.is_empty() {
+        bail!("No jets in the input");
+    }
+    Ok(jets)
 }
 
 fn main() -> Result<()> {
     color_eyre::install()?;
     let cli = Cli::parse();
 
-    let jets = cli.input.get_input()?.bytes().take_while(|b| b.iter().all(|b| !b.is_ascii_whitespace())).into_eyre().map_and_then(Direction::try_from).try_collect()?;
+    let jets = parse_jets(&std::io::read_to_string(cli.input.get_input()?)?)?;
+    let rules = cli.rules.rules()?;
 
-    if (cli.gui) {
-        let window = speedy2d::Window::new_centered("Christmas Tree Rocktris", (16 * 25, 800)).unwrap();
-        window.run_loop(Main::new(jets));
+    if cli.visualise.enabled() {
+        let stop_after = cli.stop_after;
+        cli.visualise.run(Simulator::new(rules, jets), Box::new(move |sim: &Simulator| sim.falling_rock.is_none() && sim.rock_count >= stop_after))?;
     } else {
-        let mut sim = Simulator::new(jets.clone());
-        while sim.falling_rock.is_some() || sim.rock_count < 2022 {
-            sim.step();
+        let heights = if cli.brute_force {
+            simulated_tower_heights(rules, jets, &cli.rocks)
+        } else {
+            tower_heights(rules, jets, &cli.rocks)
+        };
+        for (rocks, height) in cli.rocks.iter().zip(heights) {
+            println!("After {rocks} rocks, the tower height is {height}");
         }
+    }
 
-        println!("After 2022 rocks, the tower height is {}", sim.well.highest_occupied_line().unwrap());
+    Ok(())
+}
 
-        let total_rocks = 1000000000000u64;
-        let period = if jets.len() % 5 == 0 { jets.len() } else { jets.len() * 5 };
-        let periods = total_rocks / period as u64;
-        let rem = total_rocks % period as u64;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        let mut first_repeat: Option<Recurrence> = None;
+    const EXAMPLE: &str = ">>><<><>><<<>><>>><<<>>><<<><<<>><>><<>>\n";
 
-        let mut period_lines: Vec<usize> = vec![];
+    fn default_rules() -> Rules {
+        Rules::new(7, Shape::parse_all(Shape::DEFAULT_SHAPES).unwrap(), 3, 2).unwrap()
+    }
 
-        let mut sim = Simulator::new(jets.clone());
-        while sim.falling_rock.is_some() || sim.rock_count < total_rocks as usize {
-            // need to find when the evolution of the tower starts looping
-            if sim.falling_rock.is_none() && sim.rock_count % 1_000_000 == 0 {
-                dbg!(sim.well.lines.len());
-                dbg!(sim.rock_count);
-                dbg!(sim.well.highest_occupied_line());
-            }
-            if sim.falling_rock.is_none() && sim.rock_count % period == 0 && sim.well.highest_occupied_line().is_some() {
-                if let Some(last) = period_lines.last() {
-                    //dbg!(last, sim.well.highest_occupied_line().unwrap(), sim.well.highest_occupied_line().unwrap()-last);
-                    dbg!(sim.well.highest_occupied_line().unwrap()-last);
-                }
-                period_lines.push(sim.well.highest_occupied_line().unwrap());
-            }
-            /*
-            if sim.falling_rock.is_none() {
-                if let Some(recurrence) = &first_repeat {
-                    if sim.well.lines.len() >= recurrence.pattern_height*2{
-                        if sim.well.lines[sim.well.lines.len()-recurrence.pattern_height*2..sim.well.lines.len()]
-                            == sim.well.lines[sim.well.lines.len()-recurrence.pattern_height*4..sim.well.lines.len()-recurrence.pattern_height*2] {
-                                let recurrence_rock_period = sim.rock_count as u64 - recurrence.first_observed_rock_count as u64 ;
-                                let remaining_rocks = total_rocks - sim.rock_count as u64;
-                                let skip_recurrences = remaining_rocks / recurrence_rock_period;
-                                let skip_rocks = skip_recurrences * recurrence_rock_period;
-                                let finish_rocks = remaining_rocks - skip_rocks;
-                                let finish_rock_count = sim.rock_count + finish_rocks as usize;
-                                let skip_lines = skip_recurrences * recurrence.pattern_height as u64;
-                                let lines_here = sim.well.highest_occupied_line().unwrap();
-                                dbg!(recurrence_rock_period, remaining_rocks, skip_recurrences, skip_rocks, finish_rocks, finish_rock_count, skip_lines, lines_here);
-                                while sim.falling_rock.is_some() || sim.rock_count != finish_rock_count {
-                                    sim.step();
-                                }
-                                let total_height = sim.well.highest_occupied_line().unwrap() + skip_lines as usize;
-                                println!("The tower will be {} high", total_height);
-                                return Ok(());
-                        }
-                    }
-                }
-            }
-            if sim.falling_rock.is_none() && sim.rock_count > 2 * period && first_repeat.is_none() {
-                for pattern_height in (1..sim.well.highest_occupied_line().unwrap()/2).rev() {
-                    if sim.well.lines[sim.well.lines.len()-pattern_height..sim.well.lines.len()]
-                        == sim.well.lines[sim.well.lines.len()-pattern_height*2..sim.well.lines.len()-pattern_height] {
-                            first_repeat = Some(Recurrence {
-                                pattern_height,
-                                first_observed_rock_count: sim.rock_count,
-                                first_observed_height: sim.well.highest_occupied_line().unwrap()
-                            });
-                            dbg!(&first_repeat);
-                            break;
-                    }
-                }
-            }
-            */
-
-            if sim.falling_rock.is_none() && first_repeat.is_some() {
-                let recurrence = first_repeat.as_ref().unwrap();
-                if sim.well.lines.len() >= recurrence.first_observed_height + recurrence.periods * 2 {
-                    // check if the recurrence has recurred
-                    let first_range = sim.well.lines.len() - recurrence.periods * 2 ..sim.well.lines.len();
-                    let second_range = sim.well.lines.len() - recurrence.periods * 4..sim.well.lines.len() - recurrence.periods*2;
-                    if sim.well.lines[first_range] == sim.well.lines[second_range] {
-                        let rock_period = sim.rock_count - recurrence.first_observed_rock_count;
-                        let remaining_rocks = total_rocks as usize - sim.rock_count;
-                        let remaining_periods = remaining_rocks / rock_period;
-                        let period_lines = sim.well.lines.len() - recurrence.first_observed_height;
-                        let skip_lines = period_lines * remaining_periods;
-                        let extra_rocks = remaining_rocks - (rock_period * remaining_periods);
-                        let stop_at_rocks = sim.rock_count + extra_rocks;
-                        let current_height = sim.well.lines.len();
-                        dbg!(rock_period, remaining_rocks, remaining_periods, period_lines, skip_lines, extra_rocks, stop_at_rocks, current_height);
-                        while sim.falling_rock.is_some() || sim.rock_count < stop_at_rocks {
-                            sim.step();
-                        }
-                        let added_height = sim.well.lines.len() - current_height;
-                        let final_height = skip_lines + sim.well.lines.len();
-                        println!("The final height is {}", final_height);
-                        return Ok(())
-                    }
-                }
-            }
-            if sim.falling_rock.is_none() && sim.well.lines.len() > 2 * period && first_repeat.is_none() {
-                for repeat_len in period..sim.well.lines.len()/2 {
-                    if sim.well.lines[sim.well.lines.len() - repeat_len..sim.well.lines.len()] == sim.well.lines[sim.well.lines.len()-2*repeat_len..sim.well.lines.len()-repeat_len] {
-                        if first_repeat.is_none() {
-                            first_repeat = Some(Recurrence {
-                                first_observed_rock_count: sim.rock_count,
-                                first_observed_height: sim.well.highest_occupied_line().unwrap(),
-                                periods: repeat_len
-                            });
-                        }
-                        println!("Repeat at lines={} highest={} periods={} periods*period={} rock_count={}", sim.well.lines.len(), sim.well.highest_occupied_line().unwrap(), repeat_len, repeat_len*period, sim.rock_count);
-                    }
-                }
-            }
-            /*
-            if sim.falling_rock.is_none() && sim.rock_count > 2 * period {
-                for repeat_len in period..sim.well.lines.len()/2 {
-                    if sim.well.lines[sim.well.lines.len() - repeat_len..sim.well.lines.len()] == sim.well.lines[sim.well.lines.len()-2*repeat_len..sim.well.lines.len()-repeat_len] {
-                        println!("Repeating sequence of len {} lines at height={} rocks={}", repeat_len, sim.well.lines.len(), sim.rock_count);
-                    }
-                }
-            }
-            */
-            sim.step();
-        }
-        let height = sim.well.highest_occupied_line().unwrap() as u64;
+    #[test]
+    fn parses_default_shapes() {
+        let shapes = Shape::parse_all(Shape::DEFAULT_SHAPES).unwrap();
+        assert_eq!(shapes.iter().map(|s| (s.width, s.height())).collect_vec(), vec![(4, 1), (3, 3), (3, 3), (1, 4), (2, 2)]);
+        // the L is stored bottom line first
+        assert_eq!(shapes[2].rows, vec![0b111, 0b100, 0b100]);
+    }
 
-        println!("After a bunch of rocks the tower is {} units tall", sim.well.highest_occupied_line().unwrap());
+    #[test]
+    fn trims_art_around_the_rock_only() {
+        let shape = Shape::from_art(&["......", "..#.#.", "......", "...#..", "......"]).unwrap();
+        assert_eq!(shape, Shape { rows: vec![0b010, 0b000, 0b101], width: 3 });
     }
 
+    #[test]
+    fn rejects_bad_art() {
+        assert!(Shape::from_art(&["...", "..."]).is_err());
+        assert!(Shape::from_art(&["#x#"]).is_err());
+        assert!(Shape::from_art(&["#".repeat(Line::MAX_WIDTH + 1)]).is_err());
+        assert!(Shape::parse_all("\n").is_err());
+    }
 
-    Ok(())
+    #[test]
+    fn parses_jets() {
+        assert_eq!(parse_jets("<>\n>>").unwrap(), vec![Left, Right]);
+        assert!(parse_jets("<x>").is_err());
+        assert!(parse_jets("\n").is_err());
+    }
+
+    #[test]
+    fn tower_heights_for_the_example() {
+        let jets = parse_jets(EXAMPLE).unwrap();
+        assert_eq!(tower_heights(default_rules(), jets.clone(), &[2022, 1_000_000_000_000]), vec![3068, 1514285714288]);
+        assert_eq!(simulated_tower_heights(default_rules(), jets, &[2022]), vec![3068]);
+    }
+
+    #[test]
+    fn trimming_keeps_reachable_lines() {
+        let mut sim = Simulator::new(default_rules(), parse_jets(EXAMPLE).unwrap());
+        for _ in 0..500 {
+            sim.drop_rock();
+            assert!(sim.well.floor_offset <= sim.well.lowest_reachable_line());
+        }
+        assert!(sim.well.floor_offset > 0);
+    }
+
+    #[test]
+    fn column_that_never_fills_is_trimmed() {
+        // with rocks spawning at column 3 of 9, a column never fills, so nothing is unreachable
+        let rules = || Rules::new(9, Shape::parse_all(Shape::DEFAULT_SHAPES).unwrap(), 3, 3).unwrap();
+        let jets = parse_jets(EXAMPLE).unwrap();
+        let mut sim = Simulator::new(rules(), jets.clone());
+        for _ in 0..5000 {
+            sim.drop_rock();
+            assert!(sim.well.lines.len() <= rules().well_depth() + 8);
+        }
+        assert_eq!(
+            tower_heights(rules(), jets.clone(), &[5000, 1_000_000_000_000])[0],
+            simulated_tower_heights(rules(), jets, &[5000])[0]
+        );
+    }
 }
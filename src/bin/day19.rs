@@ -1,4 +1,4 @@
-use std::str::FromStr;
+use std::collections::HashSet;
 
 use aoc2022::prelude::*;
 use itertools::Itertools;
@@ -6,89 +6,101 @@ use itertools::Itertools;
 #[derive(Parser)]
 struct Cli {
     #[command(flatten)]
-    input: InputCLI<19>
+    input: InputCLI<19>,
+    #[arg(long, value_enum, default_value = "dfs")]
+    solver: Solver,
+    /// Resource collected by the one robot there is to begin with
+    #[arg(long, default_value = "ore")]
+    start_robot: String,
+    /// Resource to collect as much of as possible
+    #[arg(long, default_value = "geode")]
+    target: String,
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, clap::ValueEnum)]
+enum Solver {
+    /// Expand every minute's frontier of states, dropping dominated ones
+    Frontier,
+    /// Depth-first branch and bound over which robot to build next
+    Dfs,
+}
+
+/// Resources are tracked in fixed-size arrays so that states stay `Copy`
+const MAX_RESOURCES: usize = 8;
+
+type Amounts = [u32; MAX_RESOURCES];
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 struct State {
-    ore_rate: u32,
-    clay_rate: u32,
-    obsidian_rate: u32,
-    geode_rate: u32,
-    ore_count: u32,
-    clay_count: u32,
-    obsidian_count: u32,
-    geode_count: u32,
+    /// Robots collecting each resource
+    rates: Amounts,
+    /// Stock of each resource
+    counts: Amounts,
 }
 
 impl State {
     fn dominates(&self, other: &State) -> bool {
-        self.ore_rate >= other.ore_rate
-            && self.clay_rate >= other.clay_rate
-            && self.obsidian_rate >= other.obsidian_rate
-            && self.geode_rate >= other.geode_rate
-            && self.ore_count >= other.ore_count
-            && self.clay_count >= other.clay_count
-            && self.obsidian_count >= other.obsidian_count
-            && self.geode_count >= other.geode_count
-    }
-
-    const INITIAL: Self =
-        Self {
-            ore_rate: 1,
-            clay_rate: 0,
-            obsidian_rate: 0,
-            geode_rate: 0,
-            ore_count: 0,
-            clay_count: 0,
-            obsidian_count: 0,
-            geode_count: 0
-        };
+        self.rates.iter().zip(other.rates.iter()).all(|(a, b)| a >= b)
+            && self.counts.iter().zip(other.counts.iter()).all(|(a, b)| a >= b)
+    }
 
-    fn successors(&self, blueprint: &Blueprint) -> Vec<Self> {
-        let mut succs = vec![*self];
-        if self.ore_rate < blueprint.max_ore_consumption() && self.ore_count >= blueprint.ore_ore_cost {
-            succs.push(Self { ore_count: self.ore_count - blueprint.ore_ore_cost, ore_rate: self.ore_rate + 1, ..*self });
-        }
-        if self.clay_rate < blueprint.max_clay_consumption() && self.ore_count >= blueprint.clay_ore_cost {
-            succs.push(Self { ore_count: self.ore_count - blueprint.clay_ore_cost, clay_rate: self.clay_rate + 1, ..*self });
-        }
-        if self.obsidian_rate < blueprint.max_obsidian_consumption() && self.ore_count >= blueprint.obsidian_ore_cost && self.clay_count >= blueprint.obsidian_clay_cost {
-            succs.push(Self {
-                ore_count: self.ore_count - blueprint.obsidian_ore_cost,
-                clay_count: self.clay_count - blueprint.obsidian_clay_cost,
-                obsidian_rate: self.obsidian_rate + 1,
-                ..*self
-            });
-        }
-        if self.ore_count >= blueprint.geode_ore_cost && self.obsidian_count >= blueprint.geode_obsidian_cost {
-            succs.push(Self {
-                ore_count: self.ore_count - blueprint.geode_ore_cost,
-                obsidian_count: self.obsidian_count - blueprint.geode_obsidian_cost,
-                geode_rate: self.geode_rate + 1,
-                ..*self
-            });
+    fn initial(factory: &Factory) -> Self {
+        let mut rates = [0; MAX_RESOURCES];
+        rates[factory.start] = 1;
+        Self { rates, counts: [0; MAX_RESOURCES] }
+    }
+
+    fn can_afford(&self, recipe: &Recipe) -> bool {
+        recipe.costs.iter().all(|&(resource, cost)| self.counts[resource] >= cost)
+    }
+
+    /// Pay for a robot following `recipe`, which starts collecting once built
+    fn build(&self, recipe: &Recipe) -> Self {
+        let mut next = *self;
+        for &(resource, cost) in &recipe.costs {
+            next.counts[resource] -= cost;
         }
-        for succ in succs.iter_mut() {
-            succ.ore_count += self.ore_rate;
-            succ.clay_count += self.clay_rate;
-            succ.obsidian_count += self.obsidian_rate;
-            succ.geode_count += self.geode_rate;
+        next.rates[recipe.robot] += 1;
+        next
+    }
+
+    /// `into` after the robots working in `self` have collected for `minutes`
+    fn collected(&self, into: &Self, minutes: u32) -> Self {
+        let mut next = *into;
+        for (count, rate) in next.counts.iter_mut().zip(self.rates.iter()) {
+            *count += rate * minutes;
         }
-        succs
+        next
+    }
+
+    /// The states a minute on, with the robot built in that minute if any
+    fn successors(&self, factory: &Factory) -> Vec<(Self, Option<usize>)> {
+        std::iter::once((*self, None))
+            .chain(factory.blueprint.recipes.iter()
+                .filter(|recipe| factory.worth_building(self, recipe) && self.can_afford(recipe))
+                .map(|recipe| (self.build(recipe), Some(recipe.robot))))
+            .map(|(next, built)| (self.collected(&next, 1), built))
+            .collect()
     }
 }
 
+/// A robot finished at the end of a minute
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+struct Build {
+    minute: u32,
+    robot: usize,
+}
+
 #[derive(Debug)]
-struct StateCollection(Vec<State>);
+struct StateCollection(Vec<(State, Vec<Build>)>);
 
 impl StateCollection {
-    fn add(&mut self, state: State) {
-        if self.0.iter().any(|existing| existing.dominates(&state)) {
+    fn add(&mut self, state: State, builds: Vec<Build>) {
+        if self.0.iter().any(|(existing, _)| existing.dominates(&state)) {
             return ()
         }
-        self.0.retain(|existing| !state.dominates(existing));
-        self.0.push(state);
+        self.0.retain(|(existing, _)| !state.dominates(existing));
+        self.0.push((state, builds));
     }
 
     fn new() -> Self {
@@ -96,76 +108,225 @@ impl StateCollection {
     }
 }
 
-impl<T> From<T> for StateCollection
-where T: Into<Vec<State>>
-{
-    fn from(x: T) -> Self {
-        Self(x.into())
-    }
+/// A robot collecting `robot` costs the `costs`, as (resource, amount)
+#[derive(Clone, Debug)]
+struct Recipe {
+    robot: usize,
+    costs: Vec<(usize, u32)>,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 struct Blueprint {
     id: u32,
-    ore_ore_cost: u32,
-    clay_ore_cost: u32,
-    obsidian_ore_cost: u32,
-    obsidian_clay_cost: u32,
-    geode_ore_cost: u32,
-    geode_obsidian_cost: u32,
+    /// Named in order of first mention
+    resources: Vec<String>,
+    recipes: Vec<Recipe>,
 }
 
-impl FromStr for Blueprint {
-    type Err = Report;
+impl Blueprint {
+    /// Number the resources named in each recipe, given as (robot, [(amount, resource)])
+    fn new(id: u32, recipes: Vec<(&str, Vec<(u32, &str)>)>) -> Self {
+        let mut resources: Vec<String> = vec![];
+        let mut resource = |name: &str| match resources.iter().position(|r| r == name) {
+            Some(i) => i,
+            None => { resources.push(name.to_owned()); resources.len() - 1 }
+        };
+        let recipes = recipes.into_iter().map(|(robot, costs)| Recipe {
+            robot: resource(robot),
+            costs: costs.into_iter().map(|(cost, name)| (resource(name), cost)).collect(),
+        }).collect();
+        Self { id, resources, recipes }
+    }
+
+    fn resource(&self, name: &str) -> Result<usize> {
+        self.resources.iter().position(|r| r == name)
+            .ok_or_else(|| eyre!("Blueprint {} doesn't mention {}", self.id, name))
+    }
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (id, ore_ore_cost, clay_ore_cost, obsidian_ore_cost, obsidian_clay_cost, geode_ore_cost, geode_obsidian_cost) = s.split_ascii_whitespace().map(|s| s.trim_matches(|c:char| !c.is_ascii_digit())).filter(|s| !s.is_empty()).map(|s| s.parse::<u32>()).collect_tuple().ok_or_else(|| eyre!("Expected 7 numbers"))?;
-        let (id, ore_ore_cost, clay_ore_cost, obsidian_ore_cost, obsidian_clay_cost, geode_ore_cost, geode_obsidian_cost) = (id?, ore_ore_cost?, clay_ore_cost?, obsidian_ore_cost?, obsidian_clay_cost?, geode_ore_cost?, geode_obsidian_cost?);
-        Ok(Self { id, ore_ore_cost, clay_ore_cost, obsidian_ore_cost, obsidian_clay_cost, geode_ore_cost, geode_obsidian_cost, })
+    fn describe(&self, builds: &[Build]) -> String {
+        builds.iter().map(|b| format!("{} at minute {}", self.resources[b.robot], b.minute)).join(", ")
     }
 }
 
-impl Blueprint {
-    fn is_overbuild(&self, st: &State) -> bool {
-        st.ore_rate > self.max_ore_consumption()
-            || st.clay_rate > self.max_clay_consumption()
-            || st.obsidian_rate > self.max_obsidian_consumption()
+/// A blueprint with the robot to start with and the resource to collect
+struct Factory<'a> {
+    blueprint: &'a Blueprint,
+    start: usize,
+    target: usize,
+    /// The most of each resource any one robot costs. Only one robot is built per minute, so
+    /// more robots than this collecting it can't help
+    max_consumption: Amounts,
+}
+
+impl<'a> Factory<'a> {
+    fn new(blueprint: &'a Blueprint, start: &str, target: &str) -> Result<Self> {
+        if blueprint.resources.len() > MAX_RESOURCES {
+            bail!("Blueprint {} has {} resources, but at most {} are handled", blueprint.id, blueprint.resources.len(), MAX_RESOURCES);
+        }
+        let (start, target) = (blueprint.resource(start)?, blueprint.resource(target)?);
+        let mut max_consumption = [0; MAX_RESOURCES];
+        for &(resource, cost) in blueprint.recipes.iter().flat_map(|r| r.costs.iter()) {
+            max_consumption[resource] = max_consumption[resource].max(cost);
+        }
+        Ok(Self { blueprint, start, target, max_consumption })
+    }
+
+    fn worth_building(&self, state: &State, recipe: &Recipe) -> bool {
+        recipe.robot == self.target || state.rates[recipe.robot] < self.max_consumption[recipe.robot]
+    }
+
+    /// Minutes until a robot following `recipe` could be finished, if ever
+    fn minutes_to_build(&self, state: &State, recipe: &Recipe) -> Option<u32> {
+        recipe.costs.iter().try_fold(0, |wait, &(resource, cost)| {
+            let (count, rate) = (state.counts[resource], state.rates[resource]);
+            let needed = if count >= cost {
+                0
+            } else if rate == 0 {
+                return None
+            } else {
+                (cost - count).div_ceil(rate)
+            };
+            Some(wait.max(needed))
+        }).map(|wait| wait + 1)
+    }
+}
+
+/// The most of the target resource collected, and the robots built to get it
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Production {
+    collected: u32,
+    builds: Vec<Build>,
+}
+
+/// Most of the target `factory` can collect in `minutes`, by expanding the whole frontier of
+/// states each minute
+fn frontier_max_geodes(factory: &Factory, minutes: u32) -> Production {
+    let mut states = StateCollection::new();
+    states.add(State::initial(factory), vec![]);
+    for minute in 1..=minutes {
+        let mut next_states = StateCollection::new();
+        for (state, builds) in &states.0 {
+            for (next, built) in state.successors(factory) {
+                let mut builds = builds.clone();
+                builds.extend(built.map(|robot| Build { minute, robot }));
+                next_states.add(next, builds);
+            }
+        }
+        states = next_states;
     }
+    let (state, builds) = states.0.into_iter().max_by_key(|(s, _)| s.counts[factory.target]).unwrap();
+    Production { collected: state.counts[factory.target], builds }
+}
+
+/// Depth-first search over the order robots get built in, skipping the minutes spent waiting
+struct BranchAndBound<'a> {
+    factory: &'a Factory<'a>,
+    minutes: u32,
+    best: Production,
+    /// Robots built on the way to the state being searched
+    builds: Vec<Build>,
+    /// States already searched, with the minutes that were left
+    seen: HashSet<(State, u32)>,
+}
 
-    fn max_ore_consumption(&self) -> u32 {
-        u32::max(u32::max(self.geode_ore_cost, self.obsidian_ore_cost), u32::max(self.ore_ore_cost, self.clay_ore_cost))
+impl<'a> BranchAndBound<'a> {
+    fn max_geodes(factory: &'a Factory<'a>, minutes: u32) -> Production {
+        let best = Production { collected: 0, builds: vec![] };
+        let mut search = Self { factory, minutes, best, builds: vec![], seen: HashSet::new() };
+        search.visit(State::initial(factory), minutes);
+        search.best
     }
 
-    fn max_clay_consumption(&self) -> u32 {
-        self.obsidian_clay_cost
+    /// Target collected if a target robot were finished every remaining minute
+    fn optimistic_geodes(&self, state: &State, minutes_left: u32) -> u32 {
+        let target = self.factory.target;
+        state.counts[target] + state.rates[target] * minutes_left + minutes_left * minutes_left.saturating_sub(1) / 2
     }
 
-    fn max_obsidian_consumption(&self) -> u32 {
-        self.geode_obsidian_cost
+    fn visit(&mut self, state: State, minutes_left: u32) {
+        let target = self.factory.target;
+        let collected = state.counts[target] + state.rates[target] * minutes_left;
+        if collected > self.best.collected {
+            self.best = Production { collected, builds: self.builds.clone() };
+        }
+        if self.optimistic_geodes(&state, minutes_left) <= self.best.collected || !self.seen.insert((state, minutes_left)) {
+            return;
+        }
+
+        // later recipes tend to make the more valuable resources, so trying them first tightens
+        // the bound sooner
+        for recipe in self.factory.blueprint.recipes.iter().rev() {
+            if !self.factory.worth_building(&state, recipe) {
+                continue;
+            }
+            if let Some(minutes) = self.factory.minutes_to_build(&state, recipe) {
+                if minutes < minutes_left {
+                    let next = state.collected(&state, minutes).build(recipe);
+                    self.builds.push(Build { minute: self.minutes - minutes_left + minutes, robot: recipe.robot });
+                    self.visit(next, minutes_left - minutes);
+                    self.builds.pop();
+                }
+            }
+        }
     }
 }
- 
+
+/// Most of the target each factory can collect in `minutes`, solving them on separate threads
+fn max_geodes(factories: &[Factory], minutes: u32, solver: Solver) -> Vec<Production> {
+    std::thread::scope(|scope| {
+        let handles = factories.iter().map(|factory| scope.spawn(move || match solver {
+            Solver::Frontier => frontier_max_geodes(factory, minutes),
+            Solver::Dfs => BranchAndBound::max_geodes(factory, minutes),
+        })).collect_vec();
+        handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+    })
+}
+
+mod parsing {
+    use super::Blueprint;
+    use nom::IResult;
+    use nom::bytes::complete::tag;
+    use nom::character::complete::{alpha1, char, multispace0, multispace1, u32};
+    use nom::combinator::{cut, map};
+    use nom::multi::separated_list1;
+    use nom::sequence::{delimited, preceded, separated_pair, terminated, tuple};
+
+    /// A robot's resource, and the amount of each resource it costs
+    type RawRecipe<'a> = (&'a str, Vec<(u32, &'a str)>);
+
+    /// "Each X robot costs N a and M b."
+    fn recipe(s: &str) -> IResult<&str, RawRecipe<'_>> {
+        tuple((
+            delimited(tag("Each "), alpha1, tag(" robot costs ")),
+            cut(terminated(separated_list1(tag(" and "), separated_pair(u32, char(' '), alpha1)), char('.'))),
+        ))(s)
+    }
+
+    fn blueprint(s: &str) -> IResult<&str, Blueprint> {
+        let (s, id) = delimited(tag("Blueprint "), cut(u32), cut(char(':')))(s)?;
+        map(cut(separated_list1(multispace1, preceded(multispace0, recipe))), move |recipes| Blueprint::new(id, recipes))(s)
+    }
+
+    pub fn blueprints(s: &str) -> IResult<&str, Vec<Blueprint>> {
+        delimited(multispace0, separated_list1(multispace1, blueprint), multispace0)(s)
+    }
+}
+
 fn main() -> Result<()> {
     color_eyre::install()?;
     let cli = Cli::parse();
 
-    let blueprints: Vec<_> = cli.input.get_input()?.lines().into_eyre().map_and_then(|s| s.parse::<Blueprint>()).try_collect()?;
+    let input = std::io::read_to_string(cli.input.get_input()?)?;
+    let blueprints = aoc2022::iterators::parse_all(input.as_str(), parsing::blueprints)?;
+    let factories: Vec<_> = blueprints.iter().map(|b| Factory::new(b, &cli.start_robot, &cli.target)).try_collect()?;
 
     let mut quality = 0;
 
-    for blueprint in blueprints.iter() {
-        dbg!(&blueprint);
-        let mut states = vec![StateCollection::from([State::INITIAL])];
-        for _minute in 1..=24 {
-            let mut next_states = StateCollection::new();
-            states.last().unwrap().0.iter().flat_map(|s| s.successors(&blueprint)).for_each(|s| next_states.add(s));
-            dbg!(_minute, next_states.0.len());
-            //dbg!(&next_states);
-            states.push(next_states);
-        }
-        let max_geodes = states.last().unwrap().0.iter().map(|s| s.geode_count).max().unwrap();
-        let this_quality = max_geodes * blueprint.id;
-        println!("Blueprint {} produces {} geodes for {} quality", blueprint.id, max_geodes, this_quality);
+    for (factory, production) in factories.iter().zip(max_geodes(&factories, 24, cli.solver)) {
+        let blueprint = factory.blueprint;
+        let this_quality = production.collected * blueprint.id;
+        println!("Blueprint {} produces {} {}s for {} quality", blueprint.id, production.collected, cli.target, this_quality);
+        println!("  building {}", blueprint.describe(&production.builds));
         quality += this_quality;
     }
 
@@ -173,21 +334,89 @@ fn main() -> Result<()> {
 
     let mut product_product = 1;
 
-    for blueprint in blueprints[0..3].iter() {
-        let mut states = vec![StateCollection::from([State::INITIAL])];
-        for _minute in 1..=32 {
-            let mut next_states = StateCollection::new();
-            states.last().unwrap().0.iter().flat_map(|s| s.successors(&blueprint)).for_each(|s| next_states.add(s));
-            dbg!(_minute, next_states.0.len());
-            //dbg!(&next_states);
-            states.push(next_states);
-        }
-        let max_geodes = states.last().unwrap().0.iter().map(|s| s.geode_count).max().unwrap();
-        println!("Blueprint {} produces {} geodes", blueprint.id, max_geodes);
-        product_product *= max_geodes;
+    let first = &factories[..factories.len().min(3)];
+    for (factory, production) in first.iter().zip(max_geodes(first, 32, cli.solver)) {
+        let blueprint = factory.blueprint;
+        println!("Blueprint {} produces {} {}s", blueprint.id, production.collected, cli.target);
+        println!("  building {}", blueprint.describe(&production.builds));
+        product_product *= production.collected;
     }
 
     println!("Product of production is {}", product_product);
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    const EXAMPLE: &str = "\
+Blueprint 1: Each ore robot costs 4 ore. Each clay robot costs 2 ore. Each obsidian robot costs 3 ore and 14 clay. Each geode robot costs 2 ore and 7 obsidian.
+Blueprint 2:
+  Each ore robot costs 2 ore.
+  Each clay robot costs 3 ore.
+  Each obsidian robot costs 3 ore and 8 clay.
+  Each geode robot costs 3 ore and 12 obsidian.
+";
+
+    fn example() -> Vec<Blueprint> {
+        aoc2022::iterators::parse_all(EXAMPLE, parsing::blueprints).unwrap()
+    }
+
+    fn collected(blueprints: &[Blueprint], minutes: u32, solver: Solver) -> Vec<u32> {
+        let factories = blueprints.iter().map(|b| Factory::new(b, "ore", "geode").unwrap()).collect_vec();
+        max_geodes(&factories, minutes, solver).into_iter().map(|p| p.collected).collect()
+    }
+
+    #[test]
+    fn parses_recipes() {
+        let blueprints = example();
+        assert_eq!(blueprints[1].resources, ["ore", "clay", "obsidian", "geode"]);
+        assert_eq!(blueprints[1].recipes[3].robot, 3);
+        assert_eq!(blueprints[1].recipes[3].costs, vec![(0, 3), (2, 12)]);
+    }
+
+    #[test]
+    fn geodes_in_24_minutes() {
+        assert_eq!(collected(&example(), 24, Solver::Dfs), vec![9, 12]);
+    }
+
+    #[test]
+    fn geodes_in_32_minutes() {
+        assert_eq!(collected(&example(), 32, Solver::Dfs), vec![56, 62]);
+    }
+
+    #[test_case(18)]
+    #[test_case(20)]
+    fn solvers_agree(minutes: u32) {
+        assert_eq!(collected(&example(), minutes, Solver::Frontier), collected(&example(), minutes, Solver::Dfs));
+    }
+
+    #[test]
+    fn build_order_reaches_the_best() {
+        let blueprints = example();
+        let factory = Factory::new(&blueprints[0], "ore", "geode").unwrap();
+        let production = BranchAndBound::max_geodes(&factory, 24);
+        let mut state = State::initial(&factory);
+        let mut builds = production.builds.iter().peekable();
+        for minute in 1..=24 {
+            let next = match builds.next_if(|b| b.minute == minute) {
+                Some(b) => state.build(blueprints[0].recipes.iter().find(|r| r.robot == b.robot).unwrap()),
+                None => state
+            };
+            state = state.collected(&next, 1);
+        }
+        assert_eq!(state.counts[factory.target], production.collected);
+    }
+
+    #[test]
+    fn extra_resources() {
+        let blueprints = aoc2022::iterators::parse_all("\
+Blueprint 1: Each ore robot costs 2 ore. Each sand robot costs 1 ore. Each glass robot costs 2 ore and 3 sand. Each geode robot costs 1 ore and 2 glass and 2 sand.",
+            parsing::blueprints).unwrap();
+        let factory = Factory::new(&blueprints[0], "ore", "geode").unwrap();
+        assert_eq!(BranchAndBound::max_geodes(&factory, 16).collected, frontier_max_geodes(&factory, 16).collected);
+    }
+}
@@ -0,0 +1,143 @@
+use std::fmt::Display;
+
+use aoc2022::prelude::*;
+use itertools::Itertools;
+
+mod sequence;
+
+use sequence::Sequence;
+
+#[derive(Parser)]
+struct Cli {
+    #[command(flatten)]
+    input: InputCLI<20>,
+    /// Multiplies every number before mixing in part 2
+    #[arg(long, default_value_t = 811589153)]
+    decryption_key: i64,
+    /// Times to mix the file in part 2
+    #[arg(long, default_value_t = 10)]
+    rounds: usize,
+    /// Positions after the zero that make up the grove coordinates
+    #[arg(long, value_delimiter = ',', default_values_t = [1000, 2000, 3000])]
+    offsets: Vec<usize>,
+}
+
+/// The numbers in their original order, and the order mixing has put them in
+#[derive(Debug)]
+struct File {
+    numbers: Vec<i64>,
+    order: Sequence,
+}
+
+impl Display for File {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.order.iter().map(|ix| self.numbers[ix]).join(", "))
+    }
+}
+
+impl File {
+    fn new(numbers: Vec<i64>) -> Self {
+        let order = Sequence::new(numbers.len());
+        Self { numbers, order }
+    }
+
+    /// Move each number, in original order, as many places along as its value. A number moving
+    /// round the circle passes every other number once per `len - 1` places
+    fn mix(&mut self) {
+        let others = self.numbers.len() as i64 - 1;
+        if others == 0 {
+            return;
+        }
+        for (ix, &number) in self.numbers.iter().enumerate() {
+            let from = self.order.position(ix) as i64;
+            let to = (from + number.rem_euclid(others)) % others;
+            self.order.move_to(ix, to as usize);
+        }
+    }
+
+    /// The numbers `offsets` places after the zero, wrapping around
+    fn grove_coordinates(&self, offsets: &[usize]) -> Result<Vec<i64>> {
+        let zero = self.numbers.iter().position(|&n| n == 0).ok_or_else(|| eyre!("The file has no zero"))?;
+        let start = self.order.position(zero);
+        Ok(offsets.iter().map(|offset| self.numbers[self.order.at((start + offset) % self.numbers.len())]).collect())
+    }
+}
+
+/// Sum of the grove coordinates after multiplying by `key` and mixing `rounds` times
+fn decrypt(numbers: &[i64], key: i64, rounds: usize, offsets: &[usize]) -> Result<i64> {
+    let numbers = numbers.iter()
+        .map(|n| n.checked_mul(key).ok_or_else(|| eyre!("{} times the decryption key {} is too big", n, key)))
+        .try_collect()?;
+    let mut file = File::new(numbers);
+    for _ in 0..rounds {
+        file.mix();
+    }
+    file.grove_coordinates(offsets)?.into_iter()
+        .try_fold(0i64, |sum, n| sum.checked_add(n))
+        .ok_or_else(|| eyre!("The grove coordinates add up to more than fits in 64 bits"))
+}
+
+fn main() -> Result<()> {
+    color_eyre::install()?;
+    let cli = Cli::parse();
+
+    let numbers: Vec<_> = cli.input.get_input()?.lines().into_eyre().map_and_then(|s| s.parse::<i64>().map_err(Into::into)).try_collect()?;
+
+    println!("Input has {} numbers", numbers.len());
+    println!("Result is {}", decrypt(&numbers, 1, 1, &cli.offsets)?);
+    println!("Result with the decryption key is {}", decrypt(&numbers, cli.decryption_key, cli.rounds, &cli.offsets)?);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: [i64; 7] = [1, 2, -3, 3, -2, 0, 4];
+
+    /// Mix by removing and inserting into a `Vec`, one number at a time
+    fn naive_mix(numbers: &[i64], rounds: usize) -> Vec<i64> {
+        let mut order: Vec<usize> = (0..numbers.len()).collect();
+        for _ in 0..rounds {
+            for (ix, &number) in numbers.iter().enumerate() {
+                let from = order.iter().position(|&i| i == ix).unwrap();
+                order.remove(from);
+                let to = (from as i64 + number).rem_euclid(order.len() as i64) as usize;
+                order.insert(to, ix);
+            }
+        }
+        order.into_iter().map(|ix| numbers[ix]).collect()
+    }
+
+    #[test]
+    fn mixes_once() {
+        let mut file = File::new(EXAMPLE.to_vec());
+        file.mix();
+        // the puzzle starts its circle from a different place
+        assert_eq!(file.grove_coordinates(&[0, 1, 2, 3, 4, 5, 6]).unwrap(), vec![0, 3, -2, 1, 2, -3, 4]);
+        assert_eq!(decrypt(&EXAMPLE, 1, 1, &[1000, 2000, 3000]).unwrap(), 3);
+    }
+
+    #[test]
+    fn decrypts() {
+        assert_eq!(decrypt(&EXAMPLE, 811589153, 10, &[1000, 2000, 3000]).unwrap(), 1623178306);
+    }
+
+    #[test]
+    fn rejects_keys_that_overflow() {
+        assert!(decrypt(&EXAMPLE, i64::MAX / 3, 1, &[1000]).is_err());
+        // mixes to MIN, MAX, 0 without overflowing on the way
+        assert_eq!(decrypt(&[0, i64::MAX, i64::MIN], 1, 1, &[1]).unwrap(), i64::MIN);
+    }
+
+    #[test]
+    fn matches_naive_mixing() {
+        let numbers = (0..500i64).map(|i| (i * 7919) % 1001 - 500).collect_vec();
+        let mut file = File::new(numbers.clone());
+        for _ in 0..3 {
+            file.mix();
+        }
+        assert_eq!(file.order.iter().map(|ix| file.numbers[ix]).collect_vec(), naive_mix(&numbers, 3));
+    }
+}
@@ -0,0 +1,177 @@
+/// No node, in place of a child or parent index
+const NIL: usize = usize::MAX;
+
+#[derive(Clone, Debug)]
+struct Node {
+    left: usize,
+    right: usize,
+    parent: usize,
+    /// Nodes in the subtree rooted here
+    size: usize,
+    /// Heap-ordered to keep the tree balanced in expectation
+    priority: u64,
+}
+
+/// An ordering of the items `0..len` that can find an item's position and move it to another
+/// position in logarithmic time. It's an implicit treap: an item's position is the number of
+/// nodes before it in an in-order walk, so nothing stores positions that moves would invalidate
+#[derive(Clone, Debug)]
+pub struct Sequence {
+    nodes: Vec<Node>,
+    root: usize,
+}
+
+impl Sequence {
+    /// The items `0..len` in order
+    pub fn new(len: usize) -> Self {
+        // splitmix64, seeded arbitrarily; the priorities only need to look random
+        let mut seed = 0x2022_1220u64;
+        let mut random = move || {
+            seed = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+            let mut z = seed;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            z ^ (z >> 31)
+        };
+        let nodes = (0..len).map(|_| Node { left: NIL, right: NIL, parent: NIL, size: 1, priority: random() }).collect();
+        let mut sequence = Self { nodes, root: NIL };
+        for item in 0..len {
+            sequence.root = sequence.merge(sequence.root, item);
+        }
+        sequence
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    fn size(&self, node: usize) -> usize {
+        if node == NIL { 0 } else { self.nodes[node].size }
+    }
+
+    fn set_parent(&mut self, node: usize, parent: usize) {
+        if node != NIL {
+            self.nodes[node].parent = parent;
+        }
+    }
+
+    /// Recount `node`'s size and point its children back at it
+    fn update(&mut self, node: usize) {
+        let (left, right) = (self.nodes[node].left, self.nodes[node].right);
+        self.nodes[node].size = self.size(left) + self.size(right) + 1;
+        self.set_parent(left, node);
+        self.set_parent(right, node);
+    }
+
+    /// Join two trees, with every item of `a` before every item of `b`
+    fn merge(&mut self, a: usize, b: usize) -> usize {
+        if a == NIL || b == NIL {
+            let root = if a == NIL { b } else { a };
+            self.set_parent(root, NIL);
+            return root;
+        }
+        if self.nodes[a].priority > self.nodes[b].priority {
+            let right = self.merge(self.nodes[a].right, b);
+            self.nodes[a].right = right;
+            self.update(a);
+            self.nodes[a].parent = NIL;
+            a
+        } else {
+            let left = self.merge(a, self.nodes[b].left);
+            self.nodes[b].left = left;
+            self.update(b);
+            self.nodes[b].parent = NIL;
+            b
+        }
+    }
+
+    /// Split a tree into its first `count` items and the rest
+    fn split(&mut self, node: usize, count: usize) -> (usize, usize) {
+        if node == NIL {
+            return (NIL, NIL);
+        }
+        let left_size = self.size(self.nodes[node].left);
+        let (a, b) = if count <= left_size {
+            let (a, b) = self.split(self.nodes[node].left, count);
+            self.nodes[node].left = b;
+            self.update(node);
+            (a, node)
+        } else {
+            let (a, b) = self.split(self.nodes[node].right, count - left_size - 1);
+            self.nodes[node].right = a;
+            self.update(node);
+            (node, b)
+        };
+        self.set_parent(a, NIL);
+        self.set_parent(b, NIL);
+        (a, b)
+    }
+
+    /// Where `item` is in the order
+    pub fn position(&self, item: usize) -> usize {
+        let mut position = self.size(self.nodes[item].left);
+        let mut node = item;
+        while self.nodes[node].parent != NIL {
+            let parent = self.nodes[node].parent;
+            if self.nodes[parent].right == node {
+                position += self.size(self.nodes[parent].left) + 1;
+            }
+            node = parent;
+        }
+        position
+    }
+
+    /// The item at `position` in the order
+    pub fn at(&self, mut position: usize) -> usize {
+        let mut node = self.root;
+        loop {
+            let left_size = self.size(self.nodes[node].left);
+            if position < left_size {
+                node = self.nodes[node].left;
+            } else if position == left_size {
+                return node;
+            } else {
+                position -= left_size + 1;
+                node = self.nodes[node].right;
+            }
+        }
+    }
+
+    /// Take `item` out and put it back so that it ends up at `position`
+    pub fn move_to(&mut self, item: usize, position: usize) {
+        let from = self.position(item);
+        let (before, rest) = self.split(self.root, from);
+        let (_, after) = self.split(rest, 1);
+        let others = self.merge(before, after);
+        let (before, after) = self.split(others, position);
+        let with_item = self.merge(before, item);
+        self.root = self.merge(with_item, after);
+    }
+
+    /// The items in order
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.len()).map(|position| self.at(position))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn moves_match_a_vec() {
+        let len = 200;
+        let mut sequence = Sequence::new(len);
+        let mut expected: Vec<usize> = (0..len).collect();
+        for step in 0..1000 {
+            let item = (step * 37) % len;
+            let to = (step * 101 + 7) % len;
+            sequence.move_to(item, to);
+            let from = expected.iter().position(|&i| i == item).unwrap();
+            expected.remove(from);
+            expected.insert(to, item);
+            assert_eq!(sequence.position(item), to);
+        }
+        assert_eq!(sequence.iter().collect::<Vec<_>>(), expected);
+    }
+}
@@ -0,0 +1,232 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use aoc2022::prelude::*;
+
+use crate::rational::Rational;
+
+/// `coeff * x + constant` for the one unknown `x`
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Linear {
+    pub coeff: Rational,
+    pub constant: Rational,
+}
+
+impl Linear {
+    pub fn constant(value: Rational) -> Self {
+        Self { coeff: Rational::ZERO, constant: value }
+    }
+
+    pub fn unknown() -> Self {
+        Self { coeff: Rational::ONE, constant: Rational::ZERO }
+    }
+
+    /// The value, unless it depends on the unknown
+    pub fn as_constant(&self) -> Option<Rational> {
+        self.coeff.is_zero().then_some(self.constant)
+    }
+
+    /// Fails if the result's numbers don't fit
+    pub fn add(self, rhs: Self) -> Result<Self> {
+        let coeff = self.coeff.checked_add(rhs.coeff);
+        let constant = self.constant.checked_add(rhs.constant);
+        coeff.zip(constant).map(|(coeff, constant)| Self { coeff, constant })
+            .ok_or_else(|| eyre!("({}) + ({}) is too big", self, rhs))
+    }
+
+    /// Fails if the result's numbers don't fit
+    pub fn sub(self, rhs: Self) -> Result<Self> {
+        let coeff = self.coeff.checked_sub(rhs.coeff);
+        let constant = self.constant.checked_sub(rhs.constant);
+        coeff.zip(constant).map(|(coeff, constant)| Self { coeff, constant })
+            .ok_or_else(|| eyre!("({}) - ({}) is too big", self, rhs))
+    }
+
+    fn scale(self, by: Rational) -> Option<Self> {
+        Some(Self { coeff: self.coeff.checked_mul(by)?, constant: self.constant.checked_mul(by)? })
+    }
+
+    /// Fails if both sides depend on the unknown, which would make it quadratic, or if the
+    /// result's numbers don't fit
+    pub fn mul(self, rhs: Self) -> Result<Self> {
+        let product = match (self.as_constant(), rhs.as_constant()) {
+            (Some(k), _) => rhs.scale(k),
+            (_, Some(k)) => self.scale(k),
+            _ => bail!("({}) * ({}) isn't linear", self, rhs)
+        };
+        product.ok_or_else(|| eyre!("({}) * ({}) is too big", self, rhs))
+    }
+
+    /// Fails when dividing by zero or by anything that depends on the unknown, or if the
+    /// result's numbers don't fit
+    pub fn div(self, rhs: Self) -> Result<Self> {
+        let divisor = rhs.as_constant().ok_or_else(|| eyre!("({}) / ({}) isn't linear", self, rhs))?;
+        if divisor.is_zero() {
+            bail!("({}) / 0", self);
+        }
+        Rational::ONE.checked_div(divisor).and_then(|inverse| self.scale(inverse))
+            .ok_or_else(|| eyre!("({}) / ({}) is too big", self, rhs))
+    }
+}
+
+impl Display for Linear {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.coeff.is_zero() {
+            return write!(f, "{}", self.constant);
+        }
+        if self.coeff != Rational::ONE {
+            write!(f, "{}", self.coeff)?;
+        }
+        write!(f, "x")?;
+        if !self.constant.is_zero() {
+            write!(f, " + {}", self.constant)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl FromStr for Comparison {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "=" | "==" => Ok(Comparison::Eq),
+            "!=" => Ok(Comparison::Ne),
+            "<" => Ok(Comparison::Lt),
+            "<=" => Ok(Comparison::Le),
+            ">" => Ok(Comparison::Gt),
+            ">=" => Ok(Comparison::Ge),
+            _ => Err(eyre!("Expected =, !=, <, <=, > or >="))
+        }
+    }
+}
+
+impl Comparison {
+    fn holds(&self, lhs: Rational, rhs: Rational) -> bool {
+        match self {
+            Comparison::Eq => lhs == rhs,
+            Comparison::Ne => lhs != rhs,
+            Comparison::Lt => lhs < rhs,
+            Comparison::Le => lhs <= rhs,
+            Comparison::Gt => lhs > rhs,
+            Comparison::Ge => lhs >= rhs,
+        }
+    }
+
+    /// The same comparison with the sides swapped
+    fn flipped(&self) -> Self {
+        match self {
+            Comparison::Lt => Comparison::Gt,
+            Comparison::Le => Comparison::Ge,
+            Comparison::Gt => Comparison::Lt,
+            Comparison::Ge => Comparison::Le,
+            other => *other,
+        }
+    }
+
+    /// The integers the unknown can be for `lhs` to compare this way with `rhs`. Fails if the
+    /// numbers involved get too big
+    pub fn solve(&self, lhs: Linear, rhs: Linear) -> Result<Solution> {
+        // coeff * x + constant <compared to> 0
+        let diff = lhs.sub(rhs)?;
+        if diff.coeff.is_zero() {
+            return Ok(if self.holds(diff.constant, Rational::ZERO) { Solution::Any } else { Solution::None });
+        }
+        let root = diff.constant.checked_neg().and_then(|c| c.checked_div(diff.coeff))
+            .ok_or_else(|| eyre!("Solving {} for 0 gives numbers too big to handle", diff))?;
+        // dividing through by a negative coefficient flips the comparison
+        let cmp = if diff.coeff.signum() < 0 { self.flipped() } else { *self };
+        Ok(match cmp {
+            Comparison::Eq => root.to_integer().map_or(Solution::NotInteger(root), Solution::Exactly),
+            Comparison::Ne => root.to_integer().map_or(Solution::Any, Solution::AllBut),
+            Comparison::Lt => Solution::AtMost(root.ceil() - 1),
+            Comparison::Le => Solution::AtMost(root.floor()),
+            Comparison::Gt => Solution::AtLeast(root.floor() + 1),
+            Comparison::Ge => Solution::AtLeast(root.ceil()),
+        })
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Solution {
+    Exactly(i128),
+    /// The only solution is this fraction
+    NotInteger(Rational),
+    AllBut(i128),
+    AtMost(i128),
+    AtLeast(i128),
+    Any,
+    None,
+}
+
+impl Display for Solution {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Solution::Exactly(x) => write!(f, "{}", x),
+            Solution::NotInteger(x) => write!(f, "no integer (only {})", x),
+            Solution::AllBut(x) => write!(f, "anything but {}", x),
+            Solution::AtMost(x) => write!(f, "at most {}", x),
+            Solution::AtLeast(x) => write!(f, "at least {}", x),
+            Solution::Any => write!(f, "anything"),
+            Solution::None => write!(f, "nothing"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    /// 2x + 1
+    fn two_x_plus_one() -> Linear {
+        Linear { coeff: Rational::integer(2), constant: Rational::ONE }
+    }
+
+    fn constant(n: i128) -> Linear {
+        Linear::constant(Rational::integer(n))
+    }
+
+    #[test_case("=", 7, Solution::Exactly(3))]
+    #[test_case("=", 8, Solution::NotInteger(Rational::new(7, 2)))]
+    #[test_case("!=", 8, Solution::Any)]
+    #[test_case("<", 7, Solution::AtMost(2))]
+    #[test_case("<=", 8, Solution::AtMost(3))]
+    #[test_case(">", 7, Solution::AtLeast(4))]
+    #[test_case(">=", 8, Solution::AtLeast(4))]
+    fn solves(cmp: &str, rhs: i128, expected: Solution) {
+        assert_eq!(cmp.parse::<Comparison>().unwrap().solve(two_x_plus_one(), constant(rhs)).unwrap(), expected);
+    }
+
+    #[test]
+    fn negative_coefficient_flips() {
+        assert_eq!(Comparison::Lt.solve(constant(7), two_x_plus_one()).unwrap(), Solution::AtLeast(4));
+    }
+
+    #[test]
+    fn unknown_on_both_sides() {
+        // 2x + 1 = x + 5
+        let rhs = Linear::unknown().add(constant(5)).unwrap();
+        assert_eq!(Comparison::Eq.solve(two_x_plus_one(), rhs).unwrap(), Solution::Exactly(4));
+        assert_eq!(Comparison::Eq.solve(two_x_plus_one(), two_x_plus_one()).unwrap(), Solution::Any);
+        assert_eq!(Comparison::Eq.solve(two_x_plus_one(), two_x_plus_one().add(constant(1)).unwrap()).unwrap(), Solution::None);
+    }
+
+    #[test]
+    fn nonlinear() {
+        assert!(Linear::unknown().mul(Linear::unknown()).is_err());
+        assert!(constant(1).div(Linear::unknown()).is_err());
+        assert!(constant(1).div(constant(0)).is_err());
+        assert_eq!(two_x_plus_one().div(constant(2)).unwrap().to_string(), "x + 1/2");
+    }
+}
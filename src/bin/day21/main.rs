@@ -0,0 +1,240 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use aoc2022::prelude::*;
+use itertools::Itertools;
+
+mod linear;
+mod rational;
+mod troop;
+
+use linear::*;
+use rational::Rational;
+use troop::Troop;
+
+#[derive(Parser)]
+struct Cli {
+    #[command(flatten)]
+    input: InputCLI<21>,
+    /// Monkey whose number is compared
+    #[arg(long, default_value = "root")]
+    root: String,
+    /// Monkey whose number to find
+    #[arg(long, default_value = "humn")]
+    unknown: String,
+    /// How the root monkey compares its two numbers when solving for the unknown
+    #[arg(long, default_value = "=")]
+    comparison: Comparison,
+    /// Print the monkeys the root monkey waits on
+    #[arg(long, value_enum)]
+    dump: Option<Dump>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, clap::ValueEnum)]
+enum Dump {
+    /// A Graphviz graph
+    Dot,
+    /// An indented expression tree
+    Tree,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub enum Op {
+    Add,
+    Subtract,
+    Multiply,
+    Divide
+}
+
+impl FromStr for Op {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "+" => Ok(Op::Add),
+            "-" => Ok(Op::Subtract),
+            "*" => Ok(Op::Multiply),
+            "/" => Ok(Op::Divide),
+            _ => Err(eyre!("Expected +, -, *, or /"))
+        }
+    }
+}
+
+impl Display for Op {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let symbol = match self {
+            Op::Add => "+",
+            Op::Subtract => "-",
+            Op::Multiply => "*",
+            Op::Divide => "/",
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
+impl Op {
+    fn apply(&self, lhs: Linear, rhs: Linear) -> Result<Linear> {
+        match self {
+            Op::Add => lhs.add(rhs),
+            Op::Subtract => lhs.sub(rhs),
+            Op::Multiply => lhs.mul(rhs),
+            Op::Divide => lhs.div(rhs),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Expression {
+    Const(i64),
+    Binary { lhs: String, op: Op, rhs: String }
+}
+
+impl FromStr for Expression {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts = s.split_ascii_whitespace().collect_vec();
+        if parts.len() == 1 {
+            Ok(Expression::Const(parts[0].parse()?))
+        } else if parts.len() == 3 {
+            let lhs = parts[0].to_owned();
+            let rhs = parts[2].to_owned();
+            let op = parts[1].parse()?;
+            Ok(Expression::Binary { lhs, op, rhs })
+        } else {
+            Err(eyre!("Invalid expression"))
+        }
+    }
+}
+
+/// A line of input, as the monkey's name and its job
+fn parse_monkey(s: &str) -> Result<(String, Expression)> {
+    let (name, expr) = s.split(": ").collect_tuple().ok_or_else(|| eyre!("Expected '<name>: <expression>'"))?;
+    Ok((name.to_owned(), expr.parse()?))
+}
+
+/// What `root` yells as things stand
+fn root_number(troop: &Troop, root: &str) -> Result<Rational> {
+    let root = troop.id(root)?;
+    let values = troop.evaluate(root, None)?;
+    values[root].and_then(|v| v.as_constant()).ok_or_else(|| eyre!("{} has no number", troop.name(root)))
+}
+
+/// What `unknown` has to yell for `root`'s numbers to compare as asked
+fn solve(troop: &Troop, root: &str, unknown: &str, comparison: Comparison) -> Result<Solution> {
+    let (root_id, unknown) = (troop.id(root)?, troop.id(unknown)?);
+    let (lhs, rhs) = troop.operands(root_id).ok_or_else(|| eyre!("{} should be a binary op", root))?;
+    let values = troop.evaluate(root_id, Some(unknown))?;
+    comparison.solve(values[lhs].expect("evaluated"), values[rhs].expect("evaluated"))
+}
+
+fn main() -> Result<()> {
+    color_eyre::install()?;
+    let cli = Cli::parse();
+
+    let monkeys: Vec<_> = cli.input.get_input()?.lines().into_eyre().map_and_then(|s| parse_monkey(&s)).try_collect()?;
+    let troop = Troop::new(monkeys)?;
+
+    match cli.dump {
+        Some(Dump::Dot) => troop.write_dot(troop.id(&cli.root)?, &mut std::io::stdout())?,
+        Some(Dump::Tree) => troop.write_tree(troop.id(&cli.root)?, &mut std::io::stdout())?,
+        None => ()
+    }
+
+    println!("{} yells {}", cli.root, root_number(&troop, &cli.root)?);
+
+    let solution = solve(&troop, &cli.root, &cli.unknown, cli.comparison)?;
+    match solution {
+        Solution::NotInteger(_) | Solution::None => bail!("{} can't make {}'s numbers compare: {}", cli.unknown, cli.root, solution),
+        _ => println!("Solution is {}", solution),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    const EXAMPLE: &str = "\
+root: pppw + sjmn
+dbpl: 5
+cczh: sllz + lgvd
+zczc: 2
+ptdq: humn - dvpt
+dvpt: 3
+lfqf: 4
+humn: 5
+ljgn: 2
+sjmn: drzm * dbpl
+sllz: 4
+pppw: cczh / lfqf
+lgvd: ljgn * ptdq
+drzm: hmdt - zczc
+hmdt: 32
+";
+
+    fn troop(input: &str) -> Result<Troop> {
+        Troop::new(input.lines().map(parse_monkey).try_collect()?)
+    }
+
+    #[test]
+    fn root_yells() {
+        assert_eq!(root_number(&troop(EXAMPLE).unwrap(), "root").unwrap(), Rational::integer(152));
+    }
+
+    #[test_case("humn", "=", Solution::Exactly(301))]
+    #[test_case("humn", "<", Solution::AtMost(300))]
+    #[test_case("dvpt", "=", Solution::Exactly(-293))]
+    #[test_case("hmdt", "=", Solution::NotInteger(Rational::new(12, 5)))]
+    #[test_case("hmdt", ">", Solution::AtMost(2))]
+    fn solves_for(unknown: &str, comparison: &str, expected: Solution) {
+        assert_eq!(solve(&troop(EXAMPLE).unwrap(), "root", unknown, comparison.parse().unwrap()).unwrap(), expected);
+    }
+
+    #[test]
+    fn unknown_on_both_sides() {
+        // root compares 4 * (x - 3) with x + 6
+        let input = "root: a + b\na: four * c\nfour: 4\nc: x - three\nthree: 3\nb: x + six\nsix: 6\nx: 1\n";
+        assert_eq!(solve(&troop(input).unwrap(), "root", "x", Comparison::Eq).unwrap(), Solution::Exactly(6));
+    }
+
+    #[test]
+    fn reports_cycles() {
+        let err = troop("root: a + b\na: b * c\nb: 1\nc: d - b\nd: a / b\n").unwrap().order(0).unwrap_err();
+        assert_eq!(err.to_string(), "Monkeys wait on each other in a cycle: a -> c -> d -> a");
+    }
+
+    #[test]
+    fn reports_undefined_monkeys() {
+        let err = troop("root: a + b\na: 1\n").err().unwrap();
+        assert_eq!(err.to_string(), "root waits on b, but no monkey has that name");
+    }
+
+    #[test]
+    fn deep_chains() {
+        let input = (0..100_000).map(|i| format!("m{}: m{} + one\n", i, i + 1)).join("") + "m100000: 1\none: 1\n";
+        assert_eq!(root_number(&troop(&input).unwrap(), "m0").unwrap(), Rational::integer(100_001));
+    }
+
+    #[test]
+    fn reports_overflow() {
+        let input = "root: a + one\na: b / p\nb: c / p\nc: d / p\nd: e / p\ne: one / p\none: 1\np: 1000000007\n";
+        let err = root_number(&troop(input).unwrap(), "root").unwrap_err();
+        assert_eq!(err.to_string(), "working out a");
+        assert!(solve(&troop(input).unwrap(), "root", "one", Comparison::Eq).is_err());
+
+        // each quarter fits, and so does their sum, even though the common denominator doesn't
+        let input = "root: a + a\na: b / p\nb: c / p\nc: d / p\nd: one / p\none: 1\np: 1000000007\n";
+        assert_eq!(root_number(&troop(input).unwrap(), "root").unwrap(), Rational::new(2, 1_000_000_007i128.pow(4)));
+    }
+
+    #[test]
+    fn tree_dump() {
+        let troop = troop("root: a + b\na: b * b\nb: 2\n").unwrap();
+        let mut out = vec![];
+        troop.write_tree(0, &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "root: a + b\n  a: b * b\n    b: 2\n    b: 2\n  b: 2\n");
+    }
+}
@@ -0,0 +1,182 @@
+use std::cmp::Ordering;
+use std::fmt::Display;
+
+/// An exact fraction, kept in lowest terms with a positive denominator. Arithmetic is checked,
+/// giving `None` when a numerator or denominator won't fit in an `i128`
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Rational {
+    num: i128,
+    den: i128,
+}
+
+fn gcd(a: u128, b: u128) -> u128 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+/// The greatest common divisor of two values that aren't both zero, which fits unless they're
+/// both `i128::MIN` or one is `i128::MIN` and the other zero
+fn common_factor(a: i128, b: i128) -> Option<i128> {
+    i128::try_from(gcd(a.unsigned_abs(), b.unsigned_abs())).ok()
+}
+
+impl Rational {
+    pub const ZERO: Self = Self { num: 0, den: 1 };
+    pub const ONE: Self = Self { num: 1, den: 1 };
+
+    /// Panics if `den` is zero or the fraction can't be put in lowest terms
+    #[cfg(test)]
+    pub fn new(num: i128, den: i128) -> Self {
+        assert!(den != 0, "zero denominator");
+        Self::reduced(num, den).expect("fraction out of range")
+    }
+
+    /// `num / den` in lowest terms, for a non-zero `den`
+    fn reduced(num: i128, den: i128) -> Option<Self> {
+        let g = common_factor(num, den)?;
+        let (num, den) = (num / g, den / g);
+        if den < 0 {
+            Some(Self { num: num.checked_neg()?, den: den.checked_neg()? })
+        } else {
+            Some(Self { num, den })
+        }
+    }
+
+    pub fn integer(n: i128) -> Self {
+        Self { num: n, den: 1 }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.num == 0
+    }
+
+    pub fn signum(&self) -> i128 {
+        self.num.signum()
+    }
+
+    pub fn to_integer(self) -> Option<i128> {
+        (self.den == 1).then_some(self.num)
+    }
+
+    pub fn floor(&self) -> i128 {
+        self.num.div_euclid(self.den)
+    }
+
+    pub fn ceil(&self) -> i128 {
+        self.floor() + (self.num.rem_euclid(self.den) != 0) as i128
+    }
+
+    pub fn checked_neg(self) -> Option<Self> {
+        Some(Self { num: self.num.checked_neg()?, den: self.den })
+    }
+
+    pub fn checked_add(self, rhs: Self) -> Option<Self> {
+        // scaling each side up to the least common denominator keeps the numbers small
+        let g = common_factor(self.den, rhs.den)?;
+        let num = self.num.checked_mul(rhs.den / g)?.checked_add(rhs.num.checked_mul(self.den / g)?)?;
+        Self::reduced(num, (self.den / g).checked_mul(rhs.den)?)
+    }
+
+    pub fn checked_sub(self, rhs: Self) -> Option<Self> {
+        self.checked_add(rhs.checked_neg()?)
+    }
+
+    pub fn checked_mul(self, rhs: Self) -> Option<Self> {
+        // cancelling across before multiplying keeps the numbers small
+        let g1 = common_factor(self.num, rhs.den)?;
+        let g2 = common_factor(rhs.num, self.den)?;
+        Self::reduced((self.num / g1).checked_mul(rhs.num / g2)?, (self.den / g2).checked_mul(rhs.den / g1)?)
+    }
+
+    /// `None` when dividing by zero, as well as when the result is out of range
+    pub fn checked_div(self, rhs: Self) -> Option<Self> {
+        if rhs.is_zero() {
+            return None;
+        }
+        self.checked_mul(Self::reduced(rhs.den, rhs.num)?)
+    }
+}
+
+impl From<i64> for Rational {
+    fn from(n: i64) -> Self {
+        Self::integer(n.into())
+    }
+}
+
+impl PartialOrd for Rational {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Rational {
+    fn cmp(&self, other: &Self) -> Ordering {
+        if let (Some(lhs), Some(rhs)) = (self.num.checked_mul(other.den), other.num.checked_mul(self.den)) {
+            return lhs.cmp(&rhs);
+        }
+        // too big to cross-multiply, so compare the whole parts, then the fractional parts by
+        // comparing their reciprocals the other way round
+        match self.floor().cmp(&other.floor()) {
+            Ordering::Equal => {
+                let (a, b) = (self.num.rem_euclid(self.den), other.num.rem_euclid(other.den));
+                match (a, b) {
+                    (0, 0) => Ordering::Equal,
+                    (0, _) => Ordering::Less,
+                    (_, 0) => Ordering::Greater,
+                    _ => Self { num: other.den, den: b }.cmp(&Self { num: self.den, den: a })
+                }
+            },
+            unequal => unequal
+        }
+    }
+}
+
+impl Display for Rational {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.den == 1 {
+            write!(f, "{}", self.num)
+        } else {
+            write!(f, "{}/{}", self.num, self.den)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lowest_terms() {
+        assert_eq!(Rational::new(6, -4), Rational::new(-3, 2));
+        assert_eq!(Rational::new(-3, 2).to_string(), "-3/2");
+        assert_eq!(Rational::new(1, 3).checked_add(Rational::new(1, 6)), Some(Rational::new(1, 2)));
+        assert_eq!(Rational::new(2, 3).checked_div(Rational::new(4, 9)), Some(Rational::new(3, 2)));
+        assert_eq!(Rational::ONE.checked_div(Rational::ZERO), None);
+    }
+
+    #[test]
+    fn rounding() {
+        assert_eq!((Rational::new(-3, 2).floor(), Rational::new(-3, 2).ceil()), (-2, -1));
+        assert_eq!((Rational::new(7, 2).floor(), Rational::new(7, 2).ceil()), (3, 4));
+        assert_eq!(Rational::integer(5).ceil(), 5);
+        assert!(Rational::new(-1, 2) < Rational::new(1, 3));
+    }
+
+    #[test]
+    fn overflow() {
+        let big = Rational::new(1, 1_000_000_007i128.pow(4));
+        assert_eq!(big.checked_div(Rational::integer(1_000_000_007)), None);
+        assert_eq!(Rational::integer(i128::MAX).checked_add(Rational::ONE), None);
+        assert_eq!(Rational::integer(i128::MIN).checked_neg(), None);
+        // would overflow without cancelling first
+        assert_eq!(big.checked_add(big), Some(Rational::new(2, 1_000_000_007i128.pow(4))));
+        assert_eq!(big.checked_mul(Rational::integer(1_000_000_007i128.pow(4))), Some(Rational::ONE));
+    }
+
+    #[test]
+    fn compares_without_overflow() {
+        let (a, b) = (Rational::new(i128::MAX, i128::MAX - 1), Rational::new(i128::MAX - 1, i128::MAX - 2));
+        assert!(a < b);
+        assert!(Rational::new(i128::MAX, 3) > Rational::new(i128::MAX - 1, 3));
+        assert_eq!(a.cmp(&a), Ordering::Equal);
+    }
+}
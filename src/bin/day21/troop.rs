@@ -0,0 +1,162 @@
+use std::collections::HashMap;
+use std::io::Write;
+
+use aoc2022::prelude::*;
+use color_eyre::eyre::WrapErr;
+
+use crate::linear::Linear;
+use crate::{Expression, Op};
+
+#[derive(Copy, Clone, Debug)]
+enum Job {
+    Const(i64),
+    Binary { lhs: usize, op: Op, rhs: usize },
+}
+
+impl Job {
+    fn operands(&self) -> impl Iterator<Item = usize> {
+        match *self {
+            Job::Const(_) => None,
+            Job::Binary { lhs, op: _, rhs } => Some([lhs, rhs]),
+        }.into_iter().flatten()
+    }
+}
+
+/// Every monkey and the monkeys it waits on, by position in the input
+pub struct Troop {
+    names: Vec<String>,
+    ids: HashMap<String, usize>,
+    jobs: Vec<Job>,
+}
+
+impl Troop {
+    /// Fails if a name is used twice or a monkey waits on one nobody defines
+    pub fn new(monkeys: Vec<(String, Expression)>) -> Result<Self> {
+        let mut ids = HashMap::new();
+        for (id, (name, _)) in monkeys.iter().enumerate() {
+            if ids.insert(name.clone(), id).is_some() {
+                bail!("There are two monkeys named {}", name);
+            }
+        }
+        let id = |waiter: &str, name: &str| ids.get(name).copied()
+            .ok_or_else(|| eyre!("{} waits on {}, but no monkey has that name", waiter, name));
+        let jobs = monkeys.iter().map(|(name, expr)| Ok(match expr {
+            Expression::Const(v) => Job::Const(*v),
+            Expression::Binary { lhs, op, rhs } => Job::Binary { lhs: id(name, lhs)?, op: *op, rhs: id(name, rhs)? },
+        })).collect::<Result<_>>()?;
+        let names = monkeys.into_iter().map(|(name, _)| name).collect();
+        Ok(Self { names, ids, jobs })
+    }
+
+    pub fn id(&self, name: &str) -> Result<usize> {
+        self.ids.get(name).copied().ok_or_else(|| eyre!("There's no monkey named {}", name))
+    }
+
+    pub fn name(&self, id: usize) -> &str {
+        &self.names[id]
+    }
+
+    /// The monkeys `id` compares, if it's waiting on two others
+    pub fn operands(&self, id: usize) -> Option<(usize, usize)> {
+        match self.jobs[id] {
+            Job::Const(_) => None,
+            Job::Binary { lhs, op: _, rhs } => Some((lhs, rhs)),
+        }
+    }
+
+    /// `root` and every monkey it waits on, each after all the monkeys it waits on. Fails if
+    /// any of them end up waiting on themselves
+    pub fn order(&self, root: usize) -> Result<Vec<usize>> {
+        #[derive(Copy, Clone, PartialEq, Eq)]
+        enum Mark { New, Visiting, Done }
+
+        let mut marks = vec![Mark::New; self.jobs.len()];
+        let mut order = vec![];
+        // each monkey on the stack with whether its operands have been pushed yet
+        let mut stack = vec![(root, false)];
+        while let Some((id, expanded)) = stack.pop() {
+            if expanded {
+                marks[id] = Mark::Done;
+                order.push(id);
+                continue;
+            }
+            match marks[id] {
+                Mark::Done => continue,
+                Mark::Visiting => {
+                    let path: Vec<&str> = stack.iter()
+                        .filter(|&&(id, expanded)| expanded && marks[id] == Mark::Visiting)
+                        .map(|&(id, _)| self.name(id))
+                        .collect();
+                    let start = path.iter().position(|&name| name == self.name(id)).unwrap_or(0);
+                    bail!("Monkeys wait on each other in a cycle: {} -> {}", path[start..].join(" -> "), self.name(id));
+                },
+                Mark::New => {
+                    marks[id] = Mark::Visiting;
+                    stack.push((id, true));
+                    stack.extend(self.jobs[id].operands().map(|operand| (operand, false)));
+                }
+            }
+        }
+        Ok(order)
+    }
+
+    /// What `root` and each monkey it waits on yell, by id, in terms of `unknown` if given
+    pub fn evaluate(&self, root: usize, unknown: Option<usize>) -> Result<Vec<Option<Linear>>> {
+        let mut values = vec![None; self.jobs.len()];
+        for id in self.order(root)? {
+            let value = match self.jobs[id] {
+                _ if Some(id) == unknown => Linear::unknown(),
+                Job::Const(v) => Linear::constant(v.into()),
+                Job::Binary { lhs, op, rhs } => {
+                    let (lhs, rhs) = (values[lhs].expect("operands come first"), values[rhs].expect("operands come first"));
+                    op.apply(lhs, rhs).wrap_err_with(|| format!("working out {}", self.name(id)))?
+                }
+            };
+            values[id] = Some(value);
+        }
+        Ok(values)
+    }
+
+    /// Write `root` and every monkey it waits on as a Graphviz graph, with edges from each
+    /// monkey to the ones it waits on
+    pub fn write_dot(&self, root: usize, out: &mut impl Write) -> Result<()> {
+        writeln!(out, "digraph monkeys {{")?;
+        for id in self.order(root)? {
+            match self.jobs[id] {
+                Job::Const(v) => writeln!(out, "    {} [label=\"{}\\n{}\", shape=box];", self.name(id), self.name(id), v)?,
+                Job::Binary { lhs, op, rhs } => {
+                    writeln!(out, "    {} [label=\"{}\\n{}\"];", self.name(id), self.name(id), op)?;
+                    writeln!(out, "    {} -> {} [label=lhs];", self.name(id), self.name(lhs))?;
+                    writeln!(out, "    {} -> {} [label=rhs];", self.name(id), self.name(rhs))?;
+                }
+            }
+        }
+        writeln!(out, "}}")?;
+        Ok(())
+    }
+
+    /// Write `root` and every monkey it waits on as an indented tree. A monkey waited on more
+    /// than once is only written out in full the first time
+    pub fn write_tree(&self, root: usize, out: &mut impl Write) -> Result<()> {
+        // check for cycles first, which would make the tree infinite
+        self.order(root)?;
+        let mut written = vec![false; self.jobs.len()];
+        let mut stack = vec![(root, 0)];
+        while let Some((id, depth)) = stack.pop() {
+            write!(out, "{:indent$}{}: ", "", self.name(id), indent = depth * 2)?;
+            match self.jobs[id] {
+                Job::Const(v) => writeln!(out, "{}", v)?,
+                Job::Binary { lhs, op, rhs } if written[id] => {
+                    writeln!(out, "{} {} {} (see above)", self.name(lhs), op, self.name(rhs))?
+                },
+                Job::Binary { lhs, op, rhs } => {
+                    writeln!(out, "{} {} {}", self.name(lhs), op, self.name(rhs))?;
+                    stack.push((rhs, depth + 1));
+                    stack.push((lhs, depth + 1));
+                }
+            }
+            written[id] = true;
+        }
+        Ok(())
+    }
+}
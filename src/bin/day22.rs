@@ -1,12 +1,28 @@
 use std::{collections::HashMap, convert::TryFrom};
-use itertools::Itertools;
 
 use aoc2022::prelude::*;
+use aoc2022::grid;
+use aoc2022::render::{Color, RenderCLI};
+use itertools::Itertools;
 
 #[derive(Parser)]
 struct Cli {
     #[command(flatten)]
-    input: InputCLI<22>
+    input: InputCLI<22>,
+    /// How to wrap around the edges of the board, one walk each
+    #[arg(long, value_enum, value_delimiter = ',', default_values_t = [TopologyKind::Torus, TopologyKind::Cube])]
+    topology: Vec<TopologyKind>,
+    /// Edge gluing table for the table topology
+    #[arg(long)]
+    gluing: Option<std::path::PathBuf>,
+    /// Width of the faces in the gluing table
+    #[arg(long)]
+    face_size: Option<usize>,
+    /// Render the board with the route walked, as text unless `--render` says otherwise
+    #[arg(long)]
+    trace: bool,
+    #[command(flatten)]
+    render: RenderCLI,
 }
 
 #[derive(PartialEq, Eq, Copy, Clone, Debug, Hash)]
@@ -40,189 +56,347 @@ struct Board {
 }
 
 impl Board {
-    fn adjust_coord_torus(&self, coord: &mut Coord, dir: Direction) {
+    fn parse<S: AsRef<str>>(lines: &[S]) -> Result<(Board, Coord)> {
+        let mut board = Board::default();
+        let mut start_coord = None;
+
+        for (row, line) in lines.iter().enumerate() {
+            for (col, b) in line.as_ref().chars().enumerate().filter(|x| !x.1.is_whitespace()) {
+                let coord = Coord { row, col };
+                start_coord.get_or_insert(coord);
+                board.cells.insert(coord, b.try_into()?);
+                board.cols = board.cols.max(col+1);
+            }
+            board.rows = row+1;
+        }
+
+        Ok((board, start_coord.ok_or_else(|| eyre!("The board is empty"))?))
+    }
+
+    /// Follow the path from `start` facing right, stopping at walls. With `trace` every cell
+    /// visited is recorded along with the heading on leaving it
+    fn walk<T: Topology + ?Sized>(&self, topology: &T, start: Coord, path: &[Instruction], trace: bool) -> Walk {
+        let mut walk = Walk { coord: start, dir: Direction::Right, trail: vec![] };
+        for instruction in path {
+            match instruction {
+                Instruction::Forward(distance) => {
+                    for _ in 0..*distance {
+                        if trace { walk.trail.push((walk.coord, walk.dir)) }
+                        match topology.step(self, walk.coord, walk.dir) {
+                            Some((coord, dir)) if matches!(self.cells.get(&coord), Some(Cell::Open)) => {
+                                walk.coord = coord;
+                                walk.dir = dir;
+                            },
+                            _ => break
+                        }
+                    }
+                },
+                Instruction::Left => walk.dir = walk.dir.ccw(),
+                Instruction::Right => walk.dir = walk.dir.cw(),
+            }
+        }
+        if trace { walk.trail.push((walk.coord, walk.dir)) }
+        walk
+    }
+
+    fn to_grid(&self, trail: &[(Coord, Direction)]) -> grid::Grid<Tile> {
+        let mut g = grid::Grid::fill(self.cols, self.rows, Tile::Void);
+        for (coord, cell) in &self.cells {
+            g[&grid::Coord { row: coord.row, col: coord.col }] = match cell {
+                Cell::Open => Tile::Open,
+                Cell::Wall => Tile::Wall
+            };
+        }
+        for (coord, dir) in trail {
+            g[&grid::Coord { row: coord.row, col: coord.col }] = Tile::Trail(*dir);
+        }
+        g
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Tile {
+    Void,
+    Open,
+    Wall,
+    Trail(Direction)
+}
+
+impl grid::GridSquareDisplay for Tile {
+    fn cell_char(&self) -> char {
+        match self {
+            Tile::Void => ' ',
+            Tile::Open => '.',
+            Tile::Wall => '#',
+            Tile::Trail(Direction::Up) => '^',
+            Tile::Trail(Direction::Right) => '>',
+            Tile::Trail(Direction::Down) => 'v',
+            Tile::Trail(Direction::Left) => '<',
+        }
+    }
+
+    fn cell_color(&self) -> Option<Color> {
+        match self {
+            Tile::Void => Some(Color::BLACK),
+            Tile::Open => Some(Color::WHITE),
+            Tile::Wall => Some(Color::GREY),
+            Tile::Trail(_) => Some(Color::RED),
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum Instruction {
+    Forward(usize),
+    Left,
+    Right
+}
+
+struct Walk {
+    coord: Coord,
+    dir: Direction,
+    trail: Vec<(Coord, Direction)>
+}
+
+impl Walk {
+    fn password(&self) -> usize {
+        1000 * (self.coord.row+1) + 4 * (self.coord.col+1) + self.dir.code()
+    }
+}
+
+/// What happens at the edges of the board
+trait Topology {
+    /// The position and heading after one step from `coord`, or `None` if there's nowhere to go.
+    /// Walls are left to the walker
+    fn step(&self, board: &Board, coord: Coord, dir: Direction) -> Option<(Coord, Direction)>;
+}
+
+/// The edges of the board stop the walker like walls
+struct Flat;
+
+impl Topology for Flat {
+    fn step(&self, board: &Board, coord: Coord, dir: Direction) -> Option<(Coord, Direction)> {
+        coord.neighbour(dir).filter(|c| board.cells.contains_key(c)).map(|c| (c, dir))
+    }
+}
+
+/// Walking off an edge comes back on at the far side of the same row or column
+struct Torus;
+
+impl Torus {
+    fn adjust_coord(board: &Board, coord: &mut Coord, dir: Direction) {
         match dir {
             Direction::Up => {
                 if coord.row == 0 {
-                    coord.row = self.rows-1;
+                    coord.row = board.rows-1;
                 } else {
                     coord.row -= 1;
                 }
             },
             Direction::Right => {
-                coord.col = (coord.col + 1) % self.cols;
+                coord.col = (coord.col + 1) % board.cols;
             },
             Direction::Down => {
-                coord.row = (coord.row + 1) % self.rows;
+                coord.row = (coord.row + 1) % board.rows;
             },
             Direction::Left => {
                 if coord.col == 0 {
-                    coord.col = self.cols-1;
+                    coord.col = board.cols-1;
                 } else {
                     coord.col -= 1;
                 }
             }
         }
     }
+}
 
-    fn do_move_torus(&self, coord: &mut Coord, dir: Direction) -> bool {
-        let mut probe = coord.clone();
-        self.adjust_coord_torus(&mut probe, dir);
-        while !self.cells.contains_key(&probe) {
-            self.adjust_coord_torus(&mut probe, dir);
+impl Topology for Torus {
+    fn step(&self, board: &Board, coord: Coord, dir: Direction) -> Option<(Coord, Direction)> {
+        let mut probe = coord;
+        Torus::adjust_coord(board, &mut probe, dir);
+        while !board.cells.contains_key(&probe) {
+            Torus::adjust_coord(board, &mut probe, dir);
         }
-        match self.cells.get(&probe).unwrap() {
-            Cell::Open => {
-                *coord = probe;
-                true
-            },
-            Cell::Wall => false
+        Some((probe, dir))
+    }
+}
+
+/// A unit vector along one of the cube's axes
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+struct Vec3([i32; 3]);
+
+impl std::ops::Neg for Vec3 {
+    type Output = Vec3;
+
+    fn neg(self) -> Self::Output {
+        Vec3(self.0.map(|x| -x))
+    }
+}
+
+/// How a face of the net sits on the folded cube: its outward normal and the directions that
+/// the board's columns and rows increase in
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+struct Orientation {
+    normal: Vec3,
+    right: Vec3,
+    down: Vec3
+}
+
+impl Orientation {
+    /// Which way the edge in direction `dir` lies from the middle of the face
+    fn edge(&self, dir: Direction) -> Vec3 {
+        match dir {
+            Direction::Up => -self.down,
+            Direction::Right => self.right,
+            Direction::Down => self.down,
+            Direction::Left => -self.right,
         }
     }
-    
-    // part 2
-    //
-    // My cube is laid out like
-    //
-    //     aa bb
-    //    0AA1BB7
-    //    0AA1BB7
-    //     22 66
-    //    8CC6
-    //    8CC6
-    //  88 33
-    // 0EE4DD7
-    // 0EE4DD7
-    //  55 99
-    // aFF9
-    // aFF9
-    //  bb
-    //
-    
-    fn adjust_coord_cube(&self, coord: &mut Coord, dir: &mut Direction) {
+
+    /// The direction positions along the edge in direction `dir` increase in
+    fn along(&self, dir: Direction) -> Vec3 {
         match dir {
-            Direction::Up => {
-                if coord.row == 0 && 50 <= coord.col && coord.col < 100 {
-                    let edge_pos = coord.col - 50;
-                    // moving from A to F
-                    *dir = Direction::Right;
-                    coord.col = 0;
-                    coord.row = edge_pos + 150;
-                } else if coord.row == 0 && 100 <= coord.col && coord.col < 150 {
-                    let edge_pos = coord.col - 100;
-                    // moving from B to F
-                    coord.row = 199;
-                    coord.col = edge_pos;
-                } else if coord.row == 100 && 0 <= coord.col && coord.col < 50 {
-                    let edge_pos = coord.col - 0;
-                    // moving from E to C
-                    *dir = Direction::Right;
-                    coord.col = 50;
-                    coord.row = 50 + edge_pos;
-                } else {
-                    coord.row -= 1;
-                }
-            },
-            Direction::Right => {
-                if coord.col == 149 && 0 <= coord.row && coord.row < 50 {
-                    let edge_pos = coord.row - 0;
-                    // moving from B to D
-                    *dir = Direction::Left;
-                    coord.col = 99;
-                    coord.row = 149 - edge_pos;
-                } else if coord.col == 99 && 50 <= coord.row && coord.row < 100 {
-                    let edge_pos = coord.row - 50;
-                    // moving from C to B
-                    *dir = Direction::Up;
-                    coord.row = 49;
-                    coord.col = 100 + edge_pos;
-                } else if coord.col == 99 && 100 <= coord.row && coord.row < 150 {
-                    let edge_pos = coord.row - 100;
-                    // moving from D to B
-                    *dir = Direction::Left;
-                    coord.col = 149;
-                    coord.row = 49 - edge_pos;
-                } else if coord.col == 49 && 150 <= coord.row && coord.row < 200 {
-                    let edge_pos = coord.row - 150;
-                    // moving from F to D
-                    *dir = Direction::Up;
-                    coord.row = 149;
-                    coord.col = 50 + edge_pos;
-                } else {
-                    coord.col += 1;
-                }
-            },
-            Direction::Down => {
-                if coord.row == 49 && 100 <= coord.col && coord.col < 150 {
-                    let edge_pos = coord.col - 100;
-                    // moving from B to C
-                    *dir = Direction::Left;
-                    coord.col = 99;
-                    coord.row = edge_pos + 50;
-                } else if coord.row == 149 && 50 <= coord.col && coord.col < 100 {
-                    let edge_pos = coord.col - 50;
-                    // moving from D to F
-                    *dir = Direction::Left;
-                    coord.col = 49;
-                    coord.row = edge_pos + 150;
-                } else if coord.row == 199 && 0 <= coord.col && coord.col < 50 {
-                    let edge_pos = coord.col - 0;
-                    // moving from F to B
-                    coord.row = 0;
-                    coord.col = edge_pos + 100;
-                } else {
-                    coord.row += 1;
-                }
-            },
-            Direction::Left => {
-                if coord.col == 50 && 0 <= coord.row && coord.row < 50 {
-                    let edge_pos = coord.row - 0;
-                    // moving from A to E
-                    *dir = Direction::Right;
-                    coord.col = 0;
-                    coord.row = 149 - edge_pos;
-                } else if coord.col == 50 && 50 <= coord.row && coord.row < 100 {
-                    let edge_pos = coord.row - 50;
-                    // moving from C to E
-                    *dir = Direction::Down;
-                    coord.row = 100;
-                    coord.col = 0 + edge_pos;
-                } else if coord.col == 0 && 100 <= coord.row && coord.row < 150 {
-                    let edge_pos = coord.row - 100;
-                    // moving from E to A
-                    *dir = Direction::Right;
-                    coord.col = 50;
-                    coord.row = 49 - edge_pos;
-                } else if coord.col == 0 && 150 <= coord.row && coord.row < 200 {
-                    let edge_pos = coord.row - 150;
-                    // moving from F to A
-                    *dir = Direction::Down;
-                    coord.row = 0;
-                    coord.col = 50 + edge_pos;
-                } else {
-                    coord.col -= 1;
+            Direction::Up | Direction::Down => self.right,
+            Direction::Left | Direction::Right => self.down,
+        }
+    }
+
+    /// The orientation of the face folded over the edge in direction `dir`
+    fn across(&self, dir: Direction) -> Orientation {
+        match dir {
+            Direction::Up => Orientation { normal: -self.down, right: self.right, down: self.normal },
+            Direction::Right => Orientation { normal: self.right, right: -self.normal, down: self.down },
+            Direction::Down => Orientation { normal: self.down, right: self.right, down: -self.normal },
+            Direction::Left => Orientation { normal: -self.right, right: self.normal, down: self.down },
+        }
+    }
+}
+
+/// Where walking off a face over one of its edges leads
+#[derive(Copy, Clone, Debug)]
+struct Gluing {
+    face: Coord,
+    heading: Direction,
+    /// Whether positions along the edge run the opposite way on the other side
+    flipped: bool
+}
+
+/// Square faces of the board with their edges glued together, either by folding the board into a
+/// cube or from a table. Faces are identified by their position in units of the face size, so the
+/// top left face of the net might be `Coord { row: 0, col: 1 }`. Edges with no gluing lead to the
+/// neighbouring cell on the board if there is one
+struct GluedFaces {
+    size: usize,
+    wraps: HashMap<(Coord, Direction), Gluing>
+}
+
+impl GluedFaces {
+    fn fold_cube(board: &Board) -> Result<GluedFaces> {
+        let size = (1..).map(|n| (n, 6 * n * n)).find(|(_, area)| *area >= board.cells.len())
+            .filter(|(_, area)| *area == board.cells.len())
+            .ok_or_else(|| eyre!("A board with {} cells can't fold into a cube", board.cells.len()))?.0;
+
+        let net: Vec<Coord> = (0..board.rows / size)
+            .flat_map(|row| (0..board.cols / size).map(move |col| Coord { row, col }))
+            .filter(|face| board.cells.contains_key(&Coord { row: face.row * size, col: face.col * size }))
+            .collect();
+        if net.len() != 6 {
+            bail!("Found {} faces of size {} in the board, not 6", net.len(), size);
+        }
+
+        let mut faces = HashMap::new();
+        let start = Orientation { normal: Vec3([0, 0, 1]), right: Vec3([1, 0, 0]), down: Vec3([0, 1, 0]) };
+        faces.insert(net[0], start);
+        let mut queue = vec![net[0]];
+        while let Some(face) = queue.pop() {
+            let orientation = faces[&face];
+            for dir in Direction::ALL {
+                if let Some(next) = face.neighbour(dir).filter(|n| net.contains(n)) {
+                    faces.entry(next).or_insert_with(|| {
+                        queue.push(next);
+                        orientation.across(dir)
+                    });
                 }
-            },
+            }
+        }
+        if faces.len() != 6 || faces.values().map(|o| o.normal).sorted_by_key(|v| v.0).dedup().count() != 6 {
+            bail!("The faces of the board don't form a cube net");
         }
+
+        let mut wraps = HashMap::new();
+        for (face, orientation) in &faces {
+            for dir in Direction::ALL {
+                let (other, other_orientation) = faces.iter()
+                    .find(|(_, o)| o.normal == orientation.edge(dir)).unwrap();
+                let edge = Direction::ALL.into_iter()
+                    .find(|e| other_orientation.edge(*e) == orientation.normal).unwrap();
+                wraps.insert((*face, dir), Gluing {
+                    face: *other,
+                    heading: edge.opposite(),
+                    flipped: other_orientation.along(edge) != orientation.along(dir)
+                });
+            }
+        }
+
+        Ok(GluedFaces { size, wraps })
     }
 
-    fn do_move_cube(&self, coord: &mut Coord, dir: &mut Direction) -> bool {
-        let mut dnew = dir.clone();
-        let mut probe = coord.clone();
-        self.adjust_coord_cube(&mut probe, &mut dnew);
-        match self.cells.get(&probe).expect("moved to a defined cell") {
-            Cell::Open => {
-                *coord = probe;
-                *dir = dnew;
-                true
-            },
-            Cell::Wall => false
+    /// Gluings one per line, as `<face row>,<face col> <edge> <face row>,<face col> <edge>` with
+    /// `flip` on the end when positions along the two edges run in opposite directions. Leaving
+    /// the first face over its edge enters the second over its edge, and the other way around.
+    /// Positions along an edge run left to right or top to bottom on the board
+    fn from_table(size: usize, text: &str) -> Result<GluedFaces> {
+        if size == 0 {
+            bail!("Faces must be at least one cell wide");
+        }
+        let mut wraps = HashMap::new();
+        for glue in text.lines().filter(|l| !l.trim().is_empty()).parse(parsing::gluing) {
+            let ((face, edge), (other, other_edge), flipped) = glue?;
+            wraps.insert((face, edge), Gluing { face: other, heading: other_edge.opposite(), flipped });
+            wraps.insert((other, other_edge), Gluing { face, heading: edge.opposite(), flipped });
         }
+        Ok(GluedFaces { size, wraps })
     }
+}
 
+impl Topology for GluedFaces {
+    fn step(&self, board: &Board, coord: Coord, dir: Direction) -> Option<(Coord, Direction)> {
+        let n = self.size;
+        let face = Coord { row: coord.row / n, col: coord.col / n };
+        let (row, col) = (coord.row % n, coord.col % n);
+        let (leaving, pos) = match dir {
+            Direction::Up => (row == 0, col),
+            Direction::Down => (row == n - 1, col),
+            Direction::Left => (col == 0, row),
+            Direction::Right => (col == n - 1, row),
+        };
+        let gluing = match self.wraps.get(&(face, dir)) {
+            Some(gluing) if leaving => gluing,
+            _ => return Flat.step(board, coord, dir)
+        };
+        let pos = if gluing.flipped { n - 1 - pos } else { pos };
+        let (row, col) = match gluing.heading {
+            Direction::Down => (0, pos),
+            Direction::Up => (n - 1, pos),
+            Direction::Right => (pos, 0),
+            Direction::Left => (pos, n - 1),
+        };
+        Some((Coord { row: gluing.face.row * n + row, col: gluing.face.col * n + col }, gluing.heading))
+    }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq)]
+impl Coord {
+    fn neighbour(&self, dir: Direction) -> Option<Coord> {
+        match dir {
+            Direction::Up => self.row.checked_sub(1).map(|row| Coord { row, col: self.col }),
+            Direction::Right => Some(Coord { row: self.row, col: self.col + 1 }),
+            Direction::Down => Some(Coord { row: self.row + 1, col: self.col }),
+            Direction::Left => self.col.checked_sub(1).map(|col| Coord { row: self.row, col }),
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
 enum Direction {
     Up,
     Right,
@@ -231,6 +405,12 @@ enum Direction {
 }
 
 impl Direction {
+    const ALL: [Direction; 4] = [Direction::Up, Direction::Right, Direction::Down, Direction::Left];
+
+    fn opposite(self) -> Self {
+        self.cw().cw()
+    }
+
     fn cw(self) -> Self {
         match self {
             Direction::Up => Direction::Right,
@@ -259,97 +439,173 @@ impl Direction {
     }
 }
 
-fn main() -> Result<()> {
-    color_eyre::install()?;
-    let cli = Cli::parse();
-
-    let lines: Vec<_> = cli.input.get_input()?.lines().try_collect()?;
-
-    let (board_part, path_part) = lines.split(String::is_empty).collect_tuple().ok_or_else(|| eyre!("Expected two parts to the input"))?;
+mod parsing {
+    use super::{Coord, Direction, Instruction};
+    use nom::IResult;
+    use nom::branch::alt;
+    use nom::bytes::complete::tag;
+    use nom::character::complete::{char, space1, u32};
+    use nom::combinator::{map, opt, value};
+    use nom::multi::many1;
+    use nom::sequence::{preceded, separated_pair, tuple};
+
+    pub fn instructions(s: &str) -> IResult<&str, Vec<Instruction>> {
+        many1(alt((
+            map(u32, |n| Instruction::Forward(n as usize)),
+            value(Instruction::Left, char('L')),
+            value(Instruction::Right, char('R'))
+        )))(s)
+    }
 
-    let mut board = Board::default();
-    let mut start_coord = None;
+    fn face(s: &str) -> IResult<&str, Coord> {
+        map(separated_pair(u32, char(','), u32), |(row, col)| Coord { row: row as usize, col: col as usize })(s)
+    }
 
-    for (row, line) in board_part.iter().enumerate() {
-        for (col, b) in line.chars().enumerate().filter(|x| !x.1.is_whitespace()) {
-            if start_coord.is_none() {
-                start_coord.insert(Coord { row, col });
-            }
-            let cell = b.try_into()?;
-            let coord = Coord { row, col };
-            board.cells.insert(coord, cell);
-            board.cols = board.cols.max(col+1);
-        }
-        board.rows = row+1;
+    fn edge(s: &str) -> IResult<&str, Direction> {
+        alt((
+            value(Direction::Up, tag("up")),
+            value(Direction::Right, tag("right")),
+            value(Direction::Down, tag("down")),
+            value(Direction::Left, tag("left"))
+        ))(s)
     }
 
-    let path = path_part.join("");
+    fn face_edge(s: &str) -> IResult<&str, (Coord, Direction)> {
+        separated_pair(face, space1, edge)(s)
+    }
 
-    let start_coord = start_coord.expect("a starting position");
+    /// Two face edges and whether positions along them run in opposite directions
+    pub type GluedEdges = ((Coord, Direction), (Coord, Direction), bool);
 
-    {
-        let mut coord = start_coord;
-        let mut dir = Direction::Right;
+    pub fn gluing(s: &str) -> IResult<&str, GluedEdges> {
+        map(
+            tuple((face_edge, preceded(space1, face_edge), opt(preceded(space1, tag("flip"))))),
+            |(from, to, flip)| (from, to, flip.is_some())
+        )(s)
+    }
+}
 
-        for step in path.clone().split_inclusive(char::is_alphabetic) {
-            let (distance, turn): (usize, Option<&str>) = match step.rfind(char::is_alphabetic) {
-                None => (step.parse()?, None),
-                Some(i) => {
-                    let (d,t) = step.split_at(i);
-                    (d.parse()?, Some(t))
-                }
-            };
+#[derive(Copy, Clone, PartialEq, Eq, Debug, clap::ValueEnum)]
+enum TopologyKind {
+    /// Board edges are walls
+    Flat,
+    /// Rows and columns wrap around
+    Torus,
+    /// The board folded into a cube
+    Cube,
+    /// Face edges glued as listed in `--gluing`
+    Table
+}
 
-            for _ in 0..distance {
-                if !board.do_move_torus(&mut coord, dir) {
-                    break;
-                }
-            }
+fn main() -> Result<()> {
+    color_eyre::install()?;
+    let cli = Cli::parse();
 
-            match turn {
-                None => (),
-                Some("L") => dir = dir.ccw(),
-                Some("R") => dir = dir.cw(),
-                Some(_) => bail!("invalid turn")
+    let [board_part, path_part] = cli.input.get_input()?.lines().sections::<2>()?;
+    let (board, start_coord) = Board::parse(&board_part)?;
+    let path = aoc2022::iterators::parse_all(path_part.join("").trim(), parsing::instructions)?;
+
+    for kind in &cli.topology {
+        let topology: Box<dyn Topology> = match kind {
+            TopologyKind::Flat => Box::new(Flat),
+            TopologyKind::Torus => Box::new(Torus),
+            TopologyKind::Cube => Box::new(GluedFaces::fold_cube(&board)?),
+            TopologyKind::Table => {
+                let path = cli.gluing.as_ref().ok_or_else(|| eyre!("--gluing is needed for the table topology"))?;
+                let size = cli.face_size.ok_or_else(|| eyre!("--face-size is needed for the table topology"))?;
+                Box::new(GluedFaces::from_table(size, &std::fs::read_to_string(path)?)?)
             }
+        };
+        let walk = board.walk(topology.as_ref(), start_coord, &path, cli.trace);
+        println!("The password is {}", walk.password());
+        if cli.trace {
+            cli.render.render_or_print(&board.to_grid(&walk.trail), &[])?;
         }
+    }
 
-        let password = 1000 * (coord.row+1) + 4 * (coord.col+1) + dir.code();
+    Ok(())
+}
 
-        println!("The password is {}", password);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "        ...#
+        .#..
+        #...
+        ....
+...#.......#
+........#...
+..#....#....
+..........#.
+        ...#....
+        .....#..
+        .#......
+        ......#.
+
+10R5L5R10L4R5L5
+";
+
+    fn password<T: Topology>(input: &str, topology: impl FnOnce(&Board) -> T) -> usize {
+        let (board_part, path_part) = input.split_once("\n\n").unwrap();
+        let (board, start) = Board::parse(&board_part.lines().collect_vec()).unwrap();
+        let path = aoc2022::iterators::parse_all(path_part.trim(), parsing::instructions).unwrap();
+        board.walk(&topology(&board), start, &path, false).password()
     }
 
-    {
-        let mut coord = start_coord;
-        let mut dir = Direction::Right;
-
-        for step in path.clone().split_inclusive(char::is_alphabetic) {
-            let (distance, turn): (usize, Option<&str>) = match step.rfind(char::is_alphabetic) {
-                None => (step.parse()?, None),
-                Some(i) => {
-                    let (d,t) = step.split_at(i);
-                    (d.parse()?, Some(t))
-                }
-            };
+    /// An open board with a face of `size` cells wherever the layout has a `#`
+    fn net(size: usize, layout: &[&str]) -> Board {
+        let lines = layout.iter().flat_map(|row| {
+            let line: String = row.chars().map(|c| if c == '#' { ".".repeat(size) } else { " ".repeat(size) }).collect();
+            std::iter::repeat_n(line, size)
+        }).collect_vec();
+        Board::parse(&lines).unwrap().0
+    }
 
-            for _ in 0..distance {
-                if !board.do_move_cube(&mut coord, &mut dir) {
-                    break;
+    /// From every cell and heading, going `4 * size` steps goes round the cube back to the
+    /// start, and turning round after one step leads straight back
+    fn assert_folds(board: &Board) {
+        let cube = GluedFaces::fold_cube(board).unwrap();
+        for &coord in board.cells.keys() {
+            for dir in Direction::ALL {
+                let mut at = (coord, dir);
+                for _ in 0..4 * cube.size {
+                    at = cube.step(board, at.0, at.1).unwrap();
+                    assert!(board.cells.contains_key(&at.0), "stepped off the board to {:?}", at.0);
                 }
-            }
+                assert_eq!(at, (coord, dir));
 
-            match turn {
-                None => (),
-                Some("L") => dir = dir.ccw(),
-                Some("R") => dir = dir.cw(),
-                Some(_) => bail!("invalid turn")
+                let (next, heading) = cube.step(board, coord, dir).unwrap();
+                assert_eq!(cube.step(board, next, heading.opposite()).unwrap(), (coord, dir.opposite()));
             }
         }
+    }
+
+    #[test]
+    fn example_passwords() {
+        assert_eq!(password(EXAMPLE, |_| Torus), 6032);
+        assert_eq!(password(EXAMPLE, |board| GluedFaces::fold_cube(board).unwrap()), 5031);
+    }
 
-        let password = 1000 * (coord.row+1) + 4 * (coord.col+1) + dir.code();
+    #[test]
+    fn folds_the_example_net() {
+        assert_folds(&net(4, &["..#", "###", "..##"]));
+        assert_folds(&Board::parse(&EXAMPLE.lines().take(12).collect_vec()).unwrap().0);
+    }
 
-        println!("The password is {}", password);
+    #[test]
+    fn folds_other_nets() {
+        // the shape of the real puzzle input
+        assert_folds(&net(3, &[".##", ".#", "##", "#"]));
+        assert_folds(&net(2, &[".#", "####", ".#"]));
+        assert_folds(&net(2, &["##", ".###", "...#"]));
+        assert_folds(&net(1, &["###", "..###"]));
     }
 
-    Ok(())
+    #[test]
+    fn rejects_boards_that_are_not_nets() {
+        assert!(GluedFaces::fold_cube(&net(2, &["######"])).is_err());
+        assert!(GluedFaces::fold_cube(&net(2, &["###", "###"])).is_err());
+        assert!(GluedFaces::fold_cube(&net(2, &["#####"])).is_err());
+    }
 }
@@ -1,10 +1,14 @@
 use aoc2022::prelude::*;
+use aoc2022::grid;
+use aoc2022::render::{Color, RenderCLI};
 use std::collections::{HashMap,HashSet};
 
 #[derive(Parser)]
 struct Cli {
     #[command(flatten)]
-    input: InputCLI<23>
+    input: InputCLI<23>,
+    #[command(flatten)]
+    render: RenderCLI
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Hash)]
@@ -102,6 +106,28 @@ impl std::ops::Add<Direction> for &Coord {
     }
 }
 
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum Tile {
+    Ground,
+    Elf
+}
+
+impl grid::GridSquareDisplay for Tile {
+    fn cell_char(&self) -> char {
+        match self {
+            Tile::Ground => '.',
+            Tile::Elf => '#'
+        }
+    }
+
+    fn cell_color(&self) -> Option<Color> {
+        match self {
+            Tile::Ground => Some(Color::rgb(15, 40, 15)),
+            Tile::Elf => Some(Color::GREEN)
+        }
+    }
+}
+
 #[derive(Clone)]
 struct Grove {
     elf_positions: HashSet<Coord>,
@@ -206,20 +232,21 @@ impl Grove {
         self.elf_positions.len()
     }
 
-    fn dump(&self) -> std::io::Result<()> {
+    /// The occupied rectangle with a border of `margin` empty tiles on each side
+    fn to_grid(&self, margin: isize) -> grid::Grid<Tile> {
         let rect = self.occupied_rectangle();
-        let mut out = std::io::stdout();
-        for y in rect.min_y-2..=rect.max_y+2 {
-            for x in rect.min_x-2..=rect.max_x+2 {
-                if self.elf_positions.contains(&Coord { x, y }) {
-                    out.write(&[b'#'])?;
-                } else {
-                    out.write(&[b'.'])?;
-                }
-            }
-            out.write(&[b'\n'])?;
+        let mut g = grid::Grid::empty();
+        for y in rect.min_y-margin..=rect.max_y+margin {
+            let row = (rect.min_x-margin..=rect.max_x+margin)
+                .map(|x| if self.elf_positions.contains(&Coord { x, y }) { Tile::Elf } else { Tile::Ground })
+                .collect();
+            g.push_row(row).expect("rows to have the same width");
         }
-        Ok(())
+        g
+    }
+
+    fn dump(&self) -> std::io::Result<()> {
+        write!(std::io::stdout(), "{}", self.to_grid(2))
     }
 }
 
@@ -256,6 +283,7 @@ fn main() -> Result<()> {
             }
         }
         println!("No elf moves in round {}", rounds);
+        cli.render.render(&grove.to_grid(2), &[])?;
     }
 
     Ok(())
@@ -16,6 +16,7 @@ struct Cli {
 mod item {
     use color_eyre::eyre::{Report, Result, bail};
     use std::str::FromStr;
+    use aoc2022::bitset::BitSet;
 
     #[derive(Clone, Copy, Debug)]
     pub struct Item {
@@ -68,36 +69,35 @@ mod item {
 
     #[allow(clippy::derivable_impls)]
     impl Default for ItemSet {
-        fn default() -> Self { ItemSet(0) }
+        fn default() -> Self { ItemSet(BitSet::new()) }
     }
 
     impl ItemSet {
         pub fn add(&mut self, item: Item) {
-            assert!(item.priority <= 63);
-            self.0 |= 1 << item.priority
+            self.0.insert(item.priority.into());
         }
 
         #[allow(dead_code)]
-        pub fn with(self, item: Item) {
-            Self(self.0 | (1 << item.priority));
+        pub fn with(self, item: Item) -> Self {
+            Self(self.0.union(&BitSet::singleton(item.priority.into())))
         }
 
         pub fn intersection(self, other: Self) -> Self {
-            Self(self.0 & other.0)
+            Self(self.0.intersection(&other.0))
         }
 
         #[allow(dead_code)]
         pub fn intersect_with(&mut self, other: Self) {
-            self.0 &= other.0
+            *self = self.intersection(other)
         }
 
         pub fn union(self, other: Self) -> Self {
-            Self(self.0 | other.0)
+            Self(self.0.union(&other.0))
         }
 
         #[allow(dead_code)]
         pub fn union_with(&mut self, other: Self) {
-            self.0 |= other.0
+            *self = self.union(other)
         }
 
         pub fn iter(self) -> SetIter {
@@ -106,11 +106,11 @@ mod item {
     }
 
     #[derive(Debug)]
-    pub struct SetIter(bit_iter::BitIter<u64>);
+    pub struct SetIter(<u64 as BitSet>::Iter<'static>);
 
     impl SetIter {
         fn new(s: ItemSet) -> Self {
-            SetIter(bit_iter::BitIter::from(s.0))
+            SetIter(s.0.iter())
         }
     }
 
@@ -1,11 +1,11 @@
 use clap::Parser;
 use std::io::prelude::*;
 use color_eyre::eyre::{Report, Result, eyre};
-use std::ops::Range;
 use std::str::FromStr;
 use itertools::Itertools;
 
 use aoc2022::inputs::InputCLI;
+use aoc2022::intervals::IntervalSet;
 
 #[derive(Parser)]
 struct Cli {
@@ -14,15 +14,15 @@ struct Cli {
 }
 
 #[derive(Clone, Debug)]
-struct Assignment(Range<usize>);
+struct Assignment(IntervalSet<usize>);
 
 impl Assignment {
     pub fn redundant_with(&self, other: &Assignment) -> bool {
-        self.0.start >= other.0.start && self.0.end <= other.0.end
+        self.0.is_subset(&other.0)
     }
 
     pub fn overlaps(&self, other: &Assignment) -> bool {
-        self.0.start < other.0.end && self.0.end > other.0.start
+        self.0.overlaps(&other.0)
     }
 }
 
@@ -33,7 +33,7 @@ impl FromStr for Assignment {
         let (start, end) = s.split_once('-').ok_or_else(|| eyre!("No '-' in range"))?;
         let start : usize = start.parse()?;
         let end : usize = end.parse()?;
-        Ok(Assignment(start..end+1))
+        Ok(Assignment(IntervalSet::from_range(start..=end)))
     }
 }
 
@@ -1,24 +1,66 @@
-pub trait BitSet {
+pub trait BitSet: Sized {
+    type Iter<'a>: Iterator<Item = u32> where Self: 'a;
+
     fn new() -> Self;
     fn singleton(item: u32) -> Self;
     fn contains(&self, item: u32) -> bool;
     fn insert(&mut self, item: u32) -> bool;
     fn remove(&mut self, item: u32) -> bool;
+
+    fn union(&self, other: &Self) -> Self;
+    fn intersection(&self, other: &Self) -> Self;
+    fn difference(&self, other: &Self) -> Self;
+    fn is_subset(&self, other: &Self) -> bool;
+    fn len(&self) -> usize;
+    /// Members of the set in ascending order
+    fn iter(&self) -> Self::Iter<'_>;
+
+    fn is_superset(&self, other: &Self) -> bool {
+        other.is_subset(self)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn first(&self) -> Option<u32> {
+        self.iter().next()
+    }
+
+    fn last(&self) -> Option<u32> {
+        self.iter().last()
+    }
+
+    /// Like `FromIterator`, which can't be implemented for the primitive integers
+    fn from_items<I: IntoIterator<Item = u32>>(items: I) -> Self {
+        let mut s = Self::new();
+        items.into_iter().for_each(|item| { s.insert(item); });
+        s
+    }
 }
 
 pub trait BitLimits {
     const BITS: u32;
 }
 
-impl BitLimits for u64 {
-    const BITS: u32= u64::BITS;
+macro_rules! bit_limits {
+    ($($t:ty)*) => {
+        $(impl BitLimits for $t {
+            const BITS: u32 = <$t>::BITS;
+        })*
+    }
 }
 
+bit_limits! { u8 u16 u32 u64 u128 }
+
 impl<T> BitSet for T
 where
-    T: Copy + BitLimits + std::ops::BitAnd<Output = Self> + std::ops::BitOrAssign + std::ops::BitXorAssign + std::ops::Shl<u8, Output = Self> + std::ops::Not + Eq,
+    T: Copy + BitLimits + std::ops::BitAnd<Output = Self> + std::ops::BitOr<Output = Self> + std::ops::BitOrAssign + std::ops::BitXorAssign + std::ops::Shl<u8, Output = Self> + std::ops::Not<Output = Self> + Eq,
     u8: Into<T>,
+    bit_iter::BitIter<T>: From<T> + DoubleEndedIterator<Item = usize> + ExactSizeIterator,
 {
+    type Iter<'a> = std::iter::Map<bit_iter::BitIter<T>, fn(usize) -> u32> where T: 'a;
+
     fn new() -> Self {
         0.into()
     }
@@ -46,5 +88,301 @@ where
         *self ^= Self::singleton(item);
         true
     }
+
+    fn union(&self, other: &Self) -> Self {
+        *self | *other
+    }
+
+    fn intersection(&self, other: &Self) -> Self {
+        *self & *other
+    }
+
+    fn difference(&self, other: &Self) -> Self {
+        *self & !*other
+    }
+
+    fn is_subset(&self, other: &Self) -> bool {
+        self.difference(other) == 0.into()
+    }
+
+    fn len(&self) -> usize {
+        bit_iter::BitIter::from(*self).len()
+    }
+
+    fn iter(&self) -> Self::Iter<'_> {
+        bit_iter::BitIter::from(*self).map(|bit| bit as u32)
+    }
+
+    fn last(&self) -> Option<u32> {
+        self.iter().next_back()
+    }
+}
+
+const WORD_BITS: u32 = u64::BITS;
+
+fn word_and_mask(item: u32) -> (usize, u64) {
+    ((item / WORD_BITS) as usize, 1 << (item % WORD_BITS))
+}
+
+fn last_in_words(words: &[u64]) -> Option<u32> {
+    let (i, word) = words.iter().enumerate().rev().find(|(_, w)| **w != 0)?;
+    Some(i as u32 * WORD_BITS + (WORD_BITS - 1 - word.leading_zeros()))
 }
 
+/// Iterator over the members of a bitset stored as a slice of `u64` words
+pub struct Bits<'a> {
+    words: std::iter::Enumerate<std::slice::Iter<'a, u64>>,
+    current: Option<(u32, bit_iter::BitIter<u64>)>
+}
+
+impl<'a> Bits<'a> {
+    fn new(words: &'a [u64]) -> Self {
+        Self { words: words.iter().enumerate(), current: None }
+    }
+}
+
+impl<'a> Iterator for Bits<'a> {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((base, bits)) = &mut self.current {
+                if let Some(bit) = bits.next() {
+                    return Some(*base + bit as u32)
+                }
+            }
+            let (i, word) = self.words.next()?;
+            self.current = Some((i as u32 * WORD_BITS, bit_iter::BitIter::from(*word)));
+        }
+    }
+}
+
+/// A bitset of `64 * N` items backed by an array
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct FixedBitSet<const N: usize> {
+    words: [u64; N]
+}
+
+impl<const N: usize> FixedBitSet<N> {
+    pub const CAPACITY: u32 = N as u32 * WORD_BITS;
+
+    fn zip_with(&self, other: &Self, f: impl Fn(u64, u64) -> u64) -> Self {
+        let mut words = self.words;
+        words.iter_mut().zip(other.words.iter()).for_each(|(a, b)| *a = f(*a, *b));
+        Self { words }
+    }
+}
+
+impl<const N: usize> Default for FixedBitSet<N> {
+    fn default() -> Self {
+        Self { words: [0; N] }
+    }
+}
+
+impl<const N: usize> BitSet for FixedBitSet<N> {
+    type Iter<'a> = Bits<'a>;
+
+    fn new() -> Self {
+        Self::default()
+    }
+
+    fn singleton(item: u32) -> Self {
+        let mut s = Self::new();
+        s.insert(item);
+        s
+    }
+
+    fn contains(&self, item: u32) -> bool {
+        if item >= Self::CAPACITY { return false }
+        let (word, mask) = word_and_mask(item);
+        self.words[word] & mask != 0
+    }
+
+    fn insert(&mut self, item: u32) -> bool {
+        assert!(item < Self::CAPACITY);
+        let (word, mask) = word_and_mask(item);
+        let added = self.words[word] & mask == 0;
+        self.words[word] |= mask;
+        added
+    }
+
+    fn remove(&mut self, item: u32) -> bool {
+        assert!(item < Self::CAPACITY);
+        let (word, mask) = word_and_mask(item);
+        let removed = self.words[word] & mask != 0;
+        self.words[word] &= !mask;
+        removed
+    }
+
+    fn union(&self, other: &Self) -> Self {
+        self.zip_with(other, |a, b| a | b)
+    }
+
+    fn intersection(&self, other: &Self) -> Self {
+        self.zip_with(other, |a, b| a & b)
+    }
+
+    fn difference(&self, other: &Self) -> Self {
+        self.zip_with(other, |a, b| a & !b)
+    }
+
+    fn is_subset(&self, other: &Self) -> bool {
+        self.words.iter().zip(other.words.iter()).all(|(a, b)| a & !b == 0)
+    }
+
+    fn len(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    fn iter(&self) -> Bits<'_> {
+        Bits::new(&self.words)
+    }
+
+    fn last(&self) -> Option<u32> {
+        last_in_words(&self.words)
+    }
+}
+
+impl<const N: usize> FromIterator<u32> for FixedBitSet<N> {
+    fn from_iter<I: IntoIterator<Item = u32>>(iter: I) -> Self {
+        Self::from_items(iter)
+    }
+}
+
+/// A bitset that grows to hold whatever items are inserted. Trailing zero words are never stored,
+/// so equal sets always compare and hash equal
+#[derive(Clone, Default, PartialEq, Eq, Hash, Debug)]
+pub struct BitVecSet {
+    words: Vec<u64>
+}
+
+impl BitVecSet {
+    fn trim(mut self) -> Self {
+        while self.words.last() == Some(&0) {
+            self.words.pop();
+        }
+        self
+    }
+}
+
+impl BitSet for BitVecSet {
+    type Iter<'a> = Bits<'a>;
+
+    fn new() -> Self {
+        Self::default()
+    }
+
+    fn singleton(item: u32) -> Self {
+        let mut s = Self::new();
+        s.insert(item);
+        s
+    }
+
+    fn contains(&self, item: u32) -> bool {
+        let (word, mask) = word_and_mask(item);
+        self.words.get(word).is_some_and(|w| w & mask != 0)
+    }
+
+    fn insert(&mut self, item: u32) -> bool {
+        let (word, mask) = word_and_mask(item);
+        if word >= self.words.len() {
+            self.words.resize(word + 1, 0);
+        }
+        let added = self.words[word] & mask == 0;
+        self.words[word] |= mask;
+        added
+    }
+
+    fn remove(&mut self, item: u32) -> bool {
+        if !self.contains(item) { return false }
+        let (word, mask) = word_and_mask(item);
+        self.words[word] &= !mask;
+        *self = std::mem::take(self).trim();
+        true
+    }
+
+    fn union(&self, other: &Self) -> Self {
+        let (long, short) = if self.words.len() >= other.words.len() { (self, other) } else { (other, self) };
+        let mut words = long.words.clone();
+        words.iter_mut().zip(short.words.iter()).for_each(|(a, b)| *a |= b);
+        Self { words }
+    }
+
+    fn intersection(&self, other: &Self) -> Self {
+        let words = self.words.iter().zip(other.words.iter()).map(|(a, b)| a & b).collect();
+        Self { words }.trim()
+    }
+
+    fn difference(&self, other: &Self) -> Self {
+        let words = self.words.iter().enumerate()
+            .map(|(i, a)| a & !other.words.get(i).copied().unwrap_or(0))
+            .collect();
+        Self { words }.trim()
+    }
+
+    fn is_subset(&self, other: &Self) -> bool {
+        self.words.iter().enumerate()
+            .all(|(i, a)| a & !other.words.get(i).copied().unwrap_or(0) == 0)
+    }
+
+    fn len(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    fn iter(&self) -> Bits<'_> {
+        Bits::new(&self.words)
+    }
+
+    fn last(&self) -> Option<u32> {
+        last_in_words(&self.words)
+    }
+}
+
+impl FromIterator<u32> for BitVecSet {
+    fn from_iter<I: IntoIterator<Item = u32>>(iter: I) -> Self {
+        Self::from_items(iter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn integer_set_algebra() {
+        let a = u8::from_items([1, 3, 7]);
+        let b = u8::singleton(3);
+        assert_eq!(a.len(), 3);
+        assert!(b.is_subset(&a));
+        assert_eq!(a.difference(&b).iter().collect::<Vec<_>>(), vec![1, 7]);
+        assert_eq!((a.first(), a.last()), (Some(1), Some(7)));
+        assert_eq!(u128::singleton(100).union(&u128::singleton(5)).last(), Some(100));
+    }
+
+    #[test]
+    fn growable_set_algebra() {
+        let a: BitVecSet = [1, 70, 200].into_iter().collect();
+        let b: BitVecSet = [1, 70].into_iter().collect();
+        assert_eq!(a.len(), 3);
+        assert!(b.is_subset(&a));
+        assert!(!a.is_subset(&b));
+        assert_eq!(a.intersection(&b), b);
+        assert_eq!(a.union(&b), a);
+        assert_eq!(a.difference(&b).iter().collect::<Vec<_>>(), vec![200]);
+        // Removing the high item must leave a set equal to one that never had it
+        let mut c = a.clone();
+        c.remove(200);
+        assert_eq!(c, b);
+    }
+
+    #[test]
+    fn fixed_set_algebra() {
+        let a: FixedBitSet<2> = [0, 63, 64, 127].into_iter().collect();
+        let b = FixedBitSet::<2>::singleton(64);
+        assert_eq!(a.len(), 4);
+        assert!(b.is_subset(&a));
+        assert_eq!(a.difference(&b).iter().collect::<Vec<_>>(), vec![0, 63, 127]);
+        assert!(!a.contains(128));
+        assert_eq!((a.first(), a.last()), (Some(0), Some(127)));
+    }
+}
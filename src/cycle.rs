@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// A sequence that repeats: element `start + period + i` equals element `start + i` for all `i`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cycle {
+    pub start: usize,
+    pub period: usize
+}
+
+impl Cycle {
+    /// The index before or within the first repetition that is equivalent to `n`
+    pub fn reduce(&self, n: usize) -> usize {
+        if n < self.start { n } else { self.start + (n - self.start) % self.period }
+    }
+
+    /// How many whole periods `reduce` skips over for `n`
+    pub fn periods(&self, n: usize) -> usize {
+        if n < self.start { 0 } else { (n - self.start) / self.period }
+    }
+
+    /// Extend a quantity that grows by the same amount every period, such as a tower height, to
+    /// index `n`. `value_at` must be known up to `start + period`
+    pub fn extrapolate(&self, n: usize, value_at: impl Fn(usize) -> u64) -> u64 {
+        let growth = value_at(self.start + self.period) - value_at(self.start);
+        value_at(self.reduce(n)) + self.periods(n) as u64 * growth
+    }
+}
+
+/// Floyd's tortoise and hare over the sequence `x0, f(x0), f(f(x0)), ...`
+pub fn floyd<T: PartialEq, F: Fn(&T) -> T>(x0: T, f: F) -> Cycle {
+    let mut tortoise = f(&x0);
+    let mut hare = f(&tortoise);
+    while tortoise != hare {
+        tortoise = f(&tortoise);
+        hare = f(&f(&hare));
+    }
+
+    let mut start = 0;
+    let mut tortoise = x0;
+    while tortoise != hare {
+        tortoise = f(&tortoise);
+        hare = f(&hare);
+        start += 1;
+    }
+
+    let mut period = 1;
+    let mut hare = f(&tortoise);
+    while tortoise != hare {
+        hare = f(&hare);
+        period += 1;
+    }
+
+    Cycle { start, period }
+}
+
+/// Brent's algorithm, which needs fewer evaluations of `f` than `floyd`
+pub fn brent<T: PartialEq + Clone, F: Fn(&T) -> T>(x0: T, f: F) -> Cycle {
+    let mut power = 1;
+    let mut period = 1;
+    let mut tortoise = x0.clone();
+    let mut hare = f(&x0);
+    while tortoise != hare {
+        if power == period {
+            tortoise = hare.clone();
+            power *= 2;
+            period = 0;
+        }
+        hare = f(&hare);
+        period += 1;
+    }
+
+    let mut tortoise = x0.clone();
+    let mut hare = x0;
+    for _ in 0..period {
+        hare = f(&hare);
+    }
+    let mut start = 0;
+    while tortoise != hare {
+        tortoise = f(&tortoise);
+        hare = f(&hare);
+        start += 1;
+    }
+
+    Cycle { start, period }
+}
+
+/// Finds a cycle by remembering where each state key was first seen. Useful when stepping the
+/// sequence is expensive or the full state is too big to compare, and a smaller key determines
+/// everything that follows
+pub struct CycleDetector<K> {
+    first_seen: HashMap<K, usize>,
+    index: usize
+}
+
+impl<K: Hash + Eq> Default for CycleDetector<K> {
+    fn default() -> Self {
+        Self { first_seen: HashMap::new(), index: 0 }
+    }
+}
+
+impl<K: Hash + Eq> CycleDetector<K> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the key of the next element of the sequence. Returns the cycle as soon as a key
+    /// is seen for the second time, the repeat being at index `start + period`
+    pub fn observe(&mut self, key: K) -> Option<Cycle> {
+        let index = self.index;
+        self.index += 1;
+        match self.first_seen.get(&key) {
+            Some(&start) => Some(Cycle { start, period: index - start }),
+            None => {
+                self.first_seen.insert(key, index);
+                None
+            }
+        }
+    }
+
+    /// How many keys have been observed
+    pub fn observed(&self) -> usize {
+        self.index
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    // 0, 1, 2, 3, 4, 5, 3, 4, 5, ...
+    fn step(x: &u32) -> u32 {
+        if *x == 5 { 3 } else { x + 1 }
+    }
+
+    #[test]
+    fn floyd_and_brent_agree() {
+        let expected = Cycle { start: 3, period: 3 };
+        assert_eq!(floyd(0, step), expected);
+        assert_eq!(brent(0, step), expected);
+    }
+
+    #[test]
+    fn detector_finds_first_repeat() {
+        let mut detector = CycleDetector::new();
+        let mut x = 0;
+        let cycle = loop {
+            if let Some(cycle) = detector.observe(x) { break cycle }
+            x = step(&x);
+        };
+        assert_eq!(cycle, Cycle { start: 3, period: 3 });
+        assert_eq!(detector.observed(), 7);
+    }
+
+    #[test_case(2, 2, 0)]
+    #[test_case(3, 3, 0)]
+    #[test_case(7, 4, 1)]
+    #[test_case(1_000_000, 4, 333_332)]
+    fn reduce(n: usize, reduced: usize, periods: usize) {
+        let cycle = Cycle { start: 3, period: 3 };
+        assert_eq!((cycle.reduce(n), cycle.periods(n)), (reduced, periods));
+    }
+}
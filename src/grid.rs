@@ -3,6 +3,8 @@ use std::fmt::{Display, Write};
 
 use itertools::Itertools;
 
+use crate::render::Color;
+
 #[derive(Clone, Copy, Hash, PartialEq, Eq, Debug)]
 pub struct Coord {
     pub row: usize,
@@ -362,10 +364,24 @@ impl Direction {
             .chain(std::iter::once(Self::Left))
             .chain(std::iter::once(Self::Right))
     }
+
+    pub fn arrow(&self) -> char {
+        match self {
+            Self::Up => '^',
+            Self::Down => 'v',
+            Self::Left => '<',
+            Self::Right => '>'
+        }
+    }
 }
 
 impl Coord {
 
+    /// The direction of a neighbouring coordinate, or None if other is not adjacent
+    pub fn direction_to(&self, other: &Coord) -> Option<Direction> {
+        Direction::all().find(|dir| self.advanced(*dir).as_ref() == Some(other))
+    }
+
     pub fn advance(&mut self, dir: Direction) -> bool {
         match dir {
             Direction::Up => {
@@ -423,6 +439,9 @@ impl Coord {
 
 pub trait GridSquareDisplay {
     fn cell_char(&self) -> char;
+
+    /// Colour used by the renderers in `crate::render`. Plain text output ignores it
+    fn cell_color(&self) -> Option<Color> { None }
 }
 
 impl<T:GridSquareDisplay> Display for Grid<T> {
@@ -0,0 +1,373 @@
+use std::ops::{Range, RangeInclusive};
+
+use itertools::Itertools;
+
+/// Values with a next and a previous value, so that ranges of them can be converted between
+/// half-open and inclusive, and ranges that touch can be merged
+pub trait Discrete: Ord + Copy {
+    const MIN: Self;
+    const MAX: Self;
+
+    /// The next value up. Only called below `MAX`
+    fn succ(self) -> Self;
+    /// The next value down. Only called above `MIN`
+    fn pred(self) -> Self;
+    /// How many values a non-empty range holds. A `u128`, so the full range of a 64 bit type fits
+    fn count(range: &RangeInclusive<Self>) -> u128;
+}
+
+macro_rules! impl_discrete {
+    ($($t:ty)*) => {$(
+        impl Discrete for $t {
+            const MIN: Self = <$t>::MIN;
+            const MAX: Self = <$t>::MAX;
+
+            fn succ(self) -> Self {
+                self + 1
+            }
+
+            fn pred(self) -> Self {
+                self - 1
+            }
+
+            fn count(range: &RangeInclusive<Self>) -> u128 {
+                (*range.end() as i128 - *range.start() as i128 + 1) as u128
+            }
+        }
+    )*}
+}
+
+impl_discrete!(i8 i16 i32 i64 isize u8 u16 u32 u64 usize);
+
+/// A range of values that can go into an `IntervalSet`
+pub trait IntoInterval<T> {
+    /// The same values as an inclusive range, or `None` if there aren't any
+    fn into_inclusive(self) -> Option<RangeInclusive<T>>;
+}
+
+impl<T: Discrete> IntoInterval<T> for RangeInclusive<T> {
+    fn into_inclusive(self) -> Option<RangeInclusive<T>> {
+        (!self.is_empty()).then_some(self)
+    }
+}
+
+impl<T: Discrete> IntoInterval<T> for Range<T> {
+    fn into_inclusive(self) -> Option<RangeInclusive<T>> {
+        (!self.is_empty()).then(|| self.start..=self.end.pred())
+    }
+}
+
+/// A set of values stored as sorted inclusive ranges, with at least one value missing between
+/// each range and the next
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IntervalSet<T> {
+    intervals: Vec<RangeInclusive<T>>
+}
+
+impl<T: Discrete> Default for IntervalSet<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Discrete> IntervalSet<T> {
+    pub fn new() -> Self {
+        Self { intervals: Vec::new() }
+    }
+
+    /// The set of values in one range
+    pub fn from_range(range: impl IntoInterval<T>) -> Self {
+        Self { intervals: range.into_inclusive().into_iter().collect() }
+    }
+
+    /// Build from non-empty ranges sorted by start, merging any that overlap or touch
+    fn from_sorted(ranges: impl Iterator<Item = RangeInclusive<T>>) -> Self {
+        let mut intervals: Vec<RangeInclusive<T>> = Vec::new();
+        for range in ranges {
+            match intervals.last_mut() {
+                Some(last) if *last.end() == T::MAX || last.end().succ() >= *range.start() => {
+                    if range.end() > last.end() {
+                        *last = *last.start()..=*range.end();
+                    }
+                },
+                _ => intervals.push(range)
+            }
+        }
+        Self { intervals }
+    }
+
+    pub fn add(&mut self, interval: impl IntoInterval<T>) {
+        let interval = match interval.into_inclusive() {
+            Some(interval) => interval,
+            None => return
+        };
+        if self.intervals.is_empty() {
+            self.intervals.push(interval)
+        } else {
+            let left_edge = self.intervals.partition_point(|existing| existing.end() < interval.start() && existing.end().succ() < *interval.start());
+            // intervals[left_edge-1].end + 1 < interval.start
+            let right_edge = self.intervals.partition_point(|existing| existing.start() <= interval.end() || existing.start().pred() == *interval.end());
+            // intervals[right_edge].start() > interval.end + 1
+            // intervals[left_edge..right_edge] will be replaced
+            let replaced_range = left_edge..right_edge;
+            if replaced_range.is_empty() {
+                // just insert the interval
+                self.intervals.insert(left_edge, interval);
+            } else if replaced_range.len() == 1 {
+                let previous = &self.intervals[left_edge];
+                // unless the interval is already in the set, replace previous by something bigger
+                if !(previous.contains(interval.start()) && previous.contains(interval.end())) {
+                    let replacement = RangeInclusive::new(*previous.start().min(interval.start()), *previous.end().max(interval.end()));
+                    self.intervals[left_edge] = replacement;
+                }
+            } else {
+                let first = &self.intervals[left_edge];
+                let last = &self.intervals[right_edge-1];
+                let replacement =
+                    if interval.contains(first.start()) && interval.contains(last.end()) {
+                        interval
+                    } else {
+                        let start = *first.start().min(interval.start());
+                        let end = *last.end().max(interval.end());
+                        start..=end
+                    };
+                self.intervals.splice(replaced_range, std::iter::once(replacement));
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+
+    /// How many values are in the set. The intervals don't overlap, so this is at most the number
+    /// of values `T` has
+    pub fn len(&self) -> u128 {
+        self.intervals.iter().map(T::count).sum()
+    }
+
+    pub fn contains(&self, point: &T) -> bool {
+        // the last interval starting at or before the point is the only one that can hold it
+        match self.intervals.partition_point(|i| i.start() <= point) {
+            0 => false,
+            n => self.intervals[n - 1].contains(point)
+        }
+    }
+
+    /// Whether every value in `range` is in the set, which an empty range always is
+    pub fn contains_range(&self, range: impl IntoInterval<T>) -> bool {
+        let range = match range.into_inclusive() {
+            Some(range) => range,
+            None => return true
+        };
+        match self.intervals.partition_point(|i| i.start() <= range.start()) {
+            0 => false,
+            n => self.intervals[n - 1].contains(range.end())
+        }
+    }
+
+    pub fn is_subset(&self, other: &Self) -> bool {
+        self.intervals.iter().all(|interval| other.contains_range(interval.clone()))
+    }
+
+    pub fn overlaps(&self, other: &Self) -> bool {
+        !self.intersection(other).is_empty()
+    }
+
+    /// The intervals in order
+    pub fn iter(&self) -> impl Iterator<Item = &RangeInclusive<T>> {
+        self.intervals.iter()
+    }
+
+    /// Every value in the set, in order
+    pub fn values(&self) -> impl Iterator<Item = T> + '_ {
+        self.intervals.iter().flat_map(|interval| {
+            std::iter::successors(Some(*interval.start()), move |v| (v < interval.end()).then(|| v.succ()))
+        })
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        Self::from_sorted(self.intervals.iter().merge_by(other.intervals.iter(), |a, b| a.start() <= b.start()).cloned())
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut intervals = vec![];
+        let (mut i, mut j) = (0, 0);
+        while i < self.intervals.len() && j < other.intervals.len() {
+            let (a, b) = (&self.intervals[i], &other.intervals[j]);
+            let overlap = *a.start().max(b.start())..=*a.end().min(b.end());
+            if !overlap.is_empty() {
+                intervals.push(overlap);
+            }
+            // whichever ends first can't overlap anything further on
+            if a.end() < b.end() { i += 1 } else { j += 1 }
+        }
+        Self { intervals }
+    }
+
+    /// The values in `bounds` that aren't in the set
+    pub fn complement(&self, bounds: impl IntoInterval<T>) -> Self {
+        let bounds = match bounds.into_inclusive() {
+            Some(bounds) => bounds,
+            None => return Self::new()
+        };
+        let mut intervals = vec![];
+        // the first value in bounds not yet known to be in or out, if there are any left
+        let mut next = Some(*bounds.start());
+        for interval in self.intervals.iter() {
+            let from = match next {
+                Some(from) if interval.end() >= &from => from,
+                Some(_) => continue,
+                None => break
+            };
+            if *interval.start() > from {
+                let gap = from..=interval.start().pred().min(*bounds.end());
+                intervals.push(gap);
+            }
+            next = (interval.end() < bounds.end()).then(|| interval.end().succ());
+        }
+        if let Some(from) = next {
+            intervals.push(from..=*bounds.end());
+        }
+        intervals.retain(|interval| !interval.is_empty());
+        Self { intervals }
+    }
+
+    /// The values in the set that aren't in `other`
+    pub fn difference(&self, other: &Self) -> Self {
+        match (self.intervals.first(), self.intervals.last()) {
+            (Some(first), Some(last)) => self.intersection(&other.complement(*first.start()..=*last.end())),
+            _ => Self::new()
+        }
+    }
+
+    /// The ranges between one interval and the next
+    pub fn gaps(&self) -> impl Iterator<Item = RangeInclusive<T>> + '_ {
+        self.intervals.iter()
+            .tuple_windows()
+            .map(|(a, b)| a.end().succ()..=b.start().pred())
+    }
+}
+
+impl<T: Discrete, R: IntoInterval<T>> FromIterator<R> for IntervalSet<T> {
+    fn from_iter<I: IntoIterator<Item = R>>(iter: I) -> Self {
+        let mut set = Self::new();
+        iter.into_iter().for_each(|interval| set.add(interval));
+        set
+    }
+}
+
+impl<'a, T> IntoIterator for &'a IntervalSet<T> {
+    type Item = &'a RangeInclusive<T>;
+    type IntoIter = std::slice::Iter<'a, RangeInclusive<T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.intervals.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(ranges: &[RangeInclusive<i32>]) -> IntervalSet<i32> {
+        ranges.iter().cloned().collect()
+    }
+
+    /// A range with nothing in it, as `1..=0` would be
+    fn empty() -> RangeInclusive<i32> {
+        RangeInclusive::new(1, 0)
+    }
+
+    fn intervals<T: Discrete>(set: &IntervalSet<T>) -> Vec<RangeInclusive<T>> {
+        set.iter().cloned().collect()
+    }
+
+    #[test]
+    fn merges_overlapping_intervals() {
+        let mut set = set(&[5..=7, 1..=2, 6..=9, 12..=12, empty()]);
+        assert_eq!(intervals(&set), vec![1..=2, 5..=9, 12..=12]);
+
+        set.add(0..=20);
+        assert_eq!(intervals(&set), vec![0..=20]);
+    }
+
+    #[test]
+    fn merges_touching_intervals() {
+        let mut set = set(&[1..=2, 4..=4, 5..=9]);
+        assert_eq!(intervals(&set), vec![1..=2, 4..=9]);
+
+        set.add(3..3);
+        assert_eq!(intervals(&set), vec![1..=2, 4..=9]);
+        set.add(3..4);
+        assert_eq!(intervals(&set), vec![1..=9]);
+
+        set.add(i32::MIN..=i32::MIN);
+        set.add(i32::MAX - 1..=i32::MAX);
+        assert_eq!(intervals(&set), vec![i32::MIN..=i32::MIN, 1..=9, i32::MAX - 1..=i32::MAX]);
+    }
+
+    #[test]
+    fn membership() {
+        let set = set(&[1..=2, 5..=9]);
+        assert!(set.contains(&1) && set.contains(&5) && set.contains(&9));
+        assert!(!set.contains(&0) && !set.contains(&3) && !set.contains(&10));
+        assert!(set.contains_range(6..=9));
+        assert!(set.contains_range(6..10));
+        assert!(!set.contains_range(6..11));
+        assert!(!set.contains_range(2..=5));
+        assert!(set.contains_range(empty()));
+        assert!(set.contains_range(4..4));
+    }
+
+    #[test]
+    fn gaps_between_intervals() {
+        let set = set(&[1..=2, 5..=9, 12..=12]);
+        assert_eq!(set.gaps().collect::<Vec<_>>(), vec![3..=4, 10..=11]);
+    }
+
+    #[test]
+    fn length_and_values() {
+        let set = set(&[1..=2, 5..=9, 12..=12]);
+        assert_eq!(set.len(), 8);
+        assert_eq!(set.values().collect::<Vec<_>>(), vec![1, 2, 5, 6, 7, 8, 9, 12]);
+        assert_eq!((&set).into_iter().count(), 3);
+        assert!(IntervalSet::<i32>::new().is_empty());
+
+        assert_eq!(IntervalSet::from_range(i32::MIN..=i32::MAX).len(), 1 << 32);
+        assert_eq!(IntervalSet::from_range(u64::MIN..=u64::MAX).len(), 1 << 64);
+        assert_eq!(IntervalSet::from_range(i64::MIN..=i64::MAX).len(), 1 << 64);
+        assert_eq!([i64::MIN..=-1, 1..=i64::MAX].into_iter().collect::<IntervalSet<i64>>().len(), (1 << 64) - 1);
+        assert_eq!(IntervalSet::from_range(i32::MAX - 2..=i32::MAX).values().count(), 3);
+    }
+
+    #[test]
+    fn set_algebra() {
+        let a = set(&[1..=5, 10..=15]);
+        let b = set(&[4..=11, 20..=21]);
+        assert_eq!(intervals(&a.union(&b)), vec![1..=15, 20..=21]);
+        assert_eq!(intervals(&a.union(&set(&[6..=9]))), vec![1..=15]);
+        assert_eq!(intervals(&a.intersection(&b)), vec![4..=5, 10..=11]);
+        assert_eq!(intervals(&a.difference(&b)), vec![1..=3, 12..=15]);
+        assert_eq!(intervals(&b.difference(&a)), vec![6..=9, 20..=21]);
+        assert!(a.difference(&a).is_empty());
+
+        assert!(a.intersection(&b).is_subset(&a));
+        assert!(!a.is_subset(&b));
+        assert!(a.overlaps(&b) && !a.overlaps(&set(&[6..=9])));
+    }
+
+    #[test]
+    fn complement_within_bounds() {
+        let a = set(&[1..=5, 10..=15]);
+        assert_eq!(intervals(&a.complement(0..=12)), vec![0..=0, 6..=9]);
+        assert_eq!(intervals(&a.complement(0..20)), vec![0..=0, 6..=9, 16..=19]);
+        assert_eq!(intervals(&a.complement(7..=8)), vec![7..=8]);
+        assert!(a.complement(2..=4).is_empty());
+        assert!(a.complement(3..3).is_empty());
+
+        let low = IntervalSet::from_range(u8::MIN..=10);
+        assert_eq!(intervals(&low.complement(u8::MIN..=u8::MAX)), vec![11..=u8::MAX]);
+        assert!(IntervalSet::from_range(u8::MIN..=u8::MAX).complement(0..=u8::MAX).is_empty());
+    }
+}
@@ -1,3 +1,5 @@
+use color_eyre::eyre::{Report, eyre};
+
 pub trait AocItertools: Iterator {
     fn map_err<E, O, F>(self, f: F) -> MapErr<Self, F>
     where
@@ -7,10 +9,13 @@ pub trait AocItertools: Iterator {
         MapErr::new(self, f)
     }
 
-    fn parse<O, E, P>(self, p: P) -> Parse<Self, O, E, P>
+    /// Apply a nom parser to every line. The whole line has to be consumed, and failures are
+    /// reported with the line number and a caret under the failing column
+    fn parse<O, P>(self, p: P) -> Parse<Self, O, P>
     where
         Self: Sized,
-        P: nom::Parser<Self::Item, O, E>
+        Self::Item: ParseInput,
+        P: for<'a> nom::Parser<&'a str, O, nom::error::Error<&'a str>>
     {
         Parse::new(self, p)
     }
@@ -30,6 +35,37 @@ pub trait AocItertools: Iterator {
     {
         MapAndThen::new(self, f)
     }
+
+    /// Group lines into the paragraphs between blank lines. Runs of blank lines count as one
+    /// separator and the last paragraph doesn't need a trailing blank line
+    fn paragraphs(self) -> Paragraphs<Self>
+    where
+        Self: Sized,
+        Self::Item: AsRef<str>
+    {
+        Paragraphs::new(self)
+    }
+
+    /// Like `paragraphs` but for lines that may be errors, such as those from `BufRead::lines`
+    fn try_paragraphs(self) -> TryParagraphs<Self>
+    where
+        Self: Sized,
+        Self::Item: ParseInput
+    {
+        TryParagraphs::new(self)
+    }
+
+    /// Split the input into exactly `N` paragraphs
+    #[allow(clippy::type_complexity)]
+    fn sections<const N: usize>(self) -> Result<[Vec<<Self::Item as ParseInput>::Line>; N], Report>
+    where
+        Self: Sized,
+        Self::Item: ParseInput
+    {
+        let sections = self.try_paragraphs().collect::<Result<Vec<_>, _>>()?;
+        let count = sections.len();
+        sections.try_into().map_err(|_| eyre!("Expected {N} sections separated by blank lines but found {count}"))
+    }
 }
 
 impl<I:Iterator> AocItertools for I { }
@@ -101,27 +137,198 @@ where
     }
 }
 
-pub struct Parse<I, O, E, P> {
+pub struct Paragraphs<I> {
+    iter: I
+}
+
+impl<I> Paragraphs<I> {
+    fn new(iter: I) -> Self {
+        Self { iter }
+    }
+}
+
+impl<I: Iterator> Iterator for Paragraphs<I>
+where
+    I::Item: AsRef<str>
+{
+    type Item = Vec<I::Item>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut group = Vec::new();
+        for line in self.iter.by_ref() {
+            if !line.as_ref().trim().is_empty() {
+                group.push(line);
+            } else if !group.is_empty() {
+                return Some(group)
+            }
+        }
+        if group.is_empty() { None } else { Some(group) }
+    }
+}
+
+pub struct TryParagraphs<I> {
+    iter: I
+}
+
+impl<I> TryParagraphs<I> {
+    fn new(iter: I) -> Self {
+        Self { iter }
+    }
+}
+
+impl<I: Iterator> Iterator for TryParagraphs<I>
+where
+    I::Item: ParseInput
+{
+    type Item = Result<Vec<<I::Item as ParseInput>::Line>, Report>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut group = Vec::new();
+        for line in self.iter.by_ref() {
+            let line = match line.into_line() {
+                Ok(line) => line,
+                Err(e) => return Some(Err(e))
+            };
+            if !line.as_ref().trim().is_empty() {
+                group.push(line);
+            } else if !group.is_empty() {
+                return Some(Ok(group))
+            }
+        }
+        if group.is_empty() { None } else { Some(Ok(group)) }
+    }
+}
+
+/// Lines that the `AocItertools` parsing and paragraph adapters can read
+pub trait ParseInput {
+    type Line: AsRef<str>;
+
+    fn into_line(self) -> Result<Self::Line, Report>;
+}
+
+impl ParseInput for String {
+    type Line = String;
+
+    fn into_line(self) -> Result<String, Report> {
+        Ok(self)
+    }
+}
+
+impl<'s> ParseInput for &'s str {
+    type Line = &'s str;
+
+    fn into_line(self) -> Result<&'s str, Report> {
+        Ok(self)
+    }
+}
+
+impl<S: ParseInput, E: Into<Report>> ParseInput for Result<S, E> {
+    type Line = S::Line;
+
+    fn into_line(self) -> Result<S::Line, Report> {
+        self.map_err(Into::into).and_then(ParseInput::into_line)
+    }
+}
+
+/// Build a report pointing at the column where `rest` starts within `line`
+pub fn parse_error(line_num: usize, line: &str, rest: &str, what: &str) -> Report {
+    let column = line.len() - rest.len();
+    let caret = " ".repeat(line[..column].chars().count());
+    eyre!("line {line_num}, column {}: {what}\n{line}\n{caret}^", column + 1)
+}
+
+/// Apply a nom parser to a whole multi-line input, which it has to consume completely. Failures
+/// are reported like those from `AocItertools::parse`, with the line they happened on
+pub fn parse_all<'a, O, P>(input: &'a str, mut parser: P) -> Result<O, Report>
+where
+    P: nom::Parser<&'a str, O, nom::error::Error<&'a str>>
+{
+    let (rest, what) = match parser.parse(input) {
+        Ok(("", o)) => return Ok(o),
+        Ok((rest, _)) => (rest, "unexpected trailing input".to_owned()),
+        Err(nom::Err::Error(e) | nom::Err::Failure(e)) => (e.input, format!("expected {:?}", e.code)),
+        Err(nom::Err::Incomplete(_)) => ("", "incomplete input".to_owned()),
+    };
+    let offset = input.len() - rest.len();
+    let line_start = input[..offset].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = input[offset..].find('\n').map_or(input.len(), |i| offset + i);
+    let line_num = input[..offset].matches('\n').count() + 1;
+    Err(parse_error(line_num, &input[line_start..line_end], &input[offset..line_end], &what))
+}
+
+pub struct Parse<I, O, P> {
     iter: I,
     parser: P,
-    phantom: std::marker::PhantomData<(O, E)>
+    line_num: usize,
+    phantom: std::marker::PhantomData<O>
 }
 
-impl<I: Iterator, O, E, P: nom::Parser<I::Item, O, E>> Parse<I, O, E, P> {
+impl<I, O, P> Parse<I, O, P> {
     fn new(iter: I, parser: P) -> Self {
-        Self { iter, parser, phantom: std::marker::PhantomData::default() }
+        Self { iter, parser, line_num: 0, phantom: std::marker::PhantomData }
     }
 }
 
-impl<O, E, I: Iterator, P> Iterator for Parse<I, O, E, P>
+impl<O, I, P> Iterator for Parse<I, O, P>
 where
-    P: nom::Parser<I::Item, O, E>
+    I: Iterator,
+    I::Item: ParseInput,
+    P: for<'a> nom::Parser<&'a str, O, nom::error::Error<&'a str>>
 {
-    type Item = nom::IResult<I::Item, O, E>;
+    type Item = Result<O, Report>;
 
-    #[inline]
     fn next(&mut self) -> Option<Self::Item> {
-        use nom::Finish;
-        self.iter.next().map(|i| self.parser.parse(i))
+        let item = self.iter.next()?;
+        self.line_num += 1;
+        let line = match item.into_line() {
+            Ok(line) => line,
+            Err(e) => return Some(Err(e.wrap_err(format!("reading line {}", self.line_num))))
+        };
+        let line = line.as_ref();
+        Some(match self.parser.parse(line) {
+            Ok(("", o)) => Ok(o),
+            Ok((rest, _)) => Err(parse_error(self.line_num, line, rest, "unexpected trailing input")),
+            Err(nom::Err::Error(e) | nom::Err::Failure(e)) =>
+                Err(parse_error(self.line_num, line, e.input, &format!("expected {:?}", e.code))),
+            Err(nom::Err::Incomplete(_)) => Err(parse_error(self.line_num, line, "", "incomplete input")),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AocItertools;
+    use color_eyre::eyre::Result;
+
+    fn number(s: &str) -> nom::IResult<&str, u32> {
+        nom::character::complete::u32(s)
+    }
+
+    #[test]
+    fn parse_reports_failing_column() {
+        let ok: Result<Vec<u32>> = ["1", "22"].into_iter().parse(number).collect();
+        assert_eq!(ok.unwrap(), vec![1, 22]);
+
+        let err = ["1", "22x"].into_iter().parse(number).collect::<Result<Vec<u32>>>().unwrap_err();
+        assert_eq!(err.to_string(), "line 2, column 3: unexpected trailing input\n22x\n  ^");
+    }
+
+    #[test]
+    fn parse_all_reports_line() {
+        use nom::sequence::separated_pair;
+        use nom::character::complete::newline;
+
+        let err = super::parse_all("1\n2a", separated_pair(number, newline, number)).unwrap_err();
+        assert_eq!(err.to_string(), "line 2, column 2: unexpected trailing input\n2a\n ^");
+    }
+
+    #[test]
+    fn paragraphs_without_trailing_blank() {
+        let groups: Vec<_> = ["", "a", "b", "", "", "c"].into_iter().paragraphs().collect();
+        assert_eq!(groups, vec![vec!["a", "b"], vec!["c"]]);
+
+        let [first, second] = ["a", "", "b"].into_iter().sections::<2>().unwrap();
+        assert_eq!((first, second), (vec!["a"], vec!["b"]));
+        assert!(["a", "", "b"].into_iter().sections::<3>().is_err());
     }
 }
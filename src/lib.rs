@@ -2,6 +2,10 @@ pub mod inputs;
 pub mod grid;
 pub mod iterators;
 pub mod bitset;
+pub mod render;
+pub mod cycle;
+pub mod intervals;
+pub mod visualise;
 
 pub mod prelude {
     pub use clap::Parser;
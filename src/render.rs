@@ -0,0 +1,343 @@
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::{self, BufWriter, IsTerminal, Write};
+use std::path::PathBuf;
+
+use clap::{Args, ValueEnum};
+use color_eyre::eyre::{Result, bail};
+
+use crate::grid::{Coord, Grid, GridSquareDisplay};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8
+}
+
+impl Color {
+    pub const BLACK: Self = Self::rgb(0, 0, 0);
+    pub const WHITE: Self = Self::rgb(255, 255, 255);
+    pub const GREY: Self = Self::rgb(128, 128, 128);
+    pub const RED: Self = Self::rgb(220, 40, 40);
+    pub const GREEN: Self = Self::rgb(40, 180, 60);
+    pub const BLUE: Self = Self::rgb(50, 90, 220);
+    pub const YELLOW: Self = Self::rgb(230, 200, 60);
+
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b }
+    }
+
+    /// Linear interpolation between two colours, `t` is clamped to `0.0..=1.0`
+    pub fn lerp(&self, other: &Self, t: f32) -> Self {
+        let t = t.clamp(0.0, 1.0);
+        let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+        Self::rgb(mix(self.r, other.r), mix(self.g, other.g), mix(self.b, other.b))
+    }
+
+    fn hex(&self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+}
+
+/// Colour used by the image backends for cells that don't supply one
+fn fallback_color<T: GridSquareDisplay>(cell: &T) -> Color {
+    match cell.cell_char() {
+        '.' | ' ' => Color::BLACK,
+        _ => Color::WHITE
+    }
+}
+
+fn image_color<T: GridSquareDisplay>(cell: &T) -> Color {
+    cell.cell_color().unwrap_or_else(|| fallback_color(cell))
+}
+
+/// A route through the grid that is drawn on top of the cells
+#[derive(Clone, Debug)]
+pub struct PathOverlay {
+    pub coords: Vec<Coord>,
+    pub color: Color
+}
+
+impl PathOverlay {
+    pub fn new(coords: Vec<Coord>, color: Color) -> Self {
+        Self { coords, color }
+    }
+
+    /// The character to draw at each point of the path: an arrow pointing at the next step, or
+    /// `*` for the end of the path and any non-adjacent jumps
+    fn marks(&self) -> impl Iterator<Item = (Coord, char)> + '_ {
+        self.coords.iter().enumerate().map(|(i, coord)| {
+            let mark = self.coords.get(i + 1)
+                .and_then(|next| coord.direction_to(next))
+                .map_or('*', |dir| dir.arrow());
+            (*coord, mark)
+        })
+    }
+}
+
+/// Overlay marks indexed by grid position. Later overlays win where paths cross
+fn overlay_grid<T>(grid: &Grid<T>, overlays: &[PathOverlay]) -> Grid<Option<(char, Color)>> {
+    let mut marks = Grid::fill(grid.width(), grid.height(), None);
+    for overlay in overlays {
+        for (coord, mark) in overlay.marks() {
+            if let Some(cell) = marks.get_mut(&coord) {
+                *cell = Some((mark, overlay.color));
+            }
+        }
+    }
+    marks
+}
+
+pub trait Renderer {
+    fn render<T: GridSquareDisplay>(&self, grid: &Grid<T>, overlays: &[PathOverlay], out: &mut dyn Write) -> Result<()>;
+}
+
+/// Plain characters, the same as the `Display` impl on `Grid` but with overlays
+pub struct TextRenderer;
+
+impl Renderer for TextRenderer {
+    fn render<T: GridSquareDisplay>(&self, grid: &Grid<T>, overlays: &[PathOverlay], out: &mut dyn Write) -> Result<()> {
+        let marks = overlay_grid(grid, overlays);
+        for (r, m) in grid.rows().zip(marks.rows()) {
+            let line: String = r.iter().zip(m.iter())
+                .map(|(cell, mark)| mark.map_or_else(|| cell.cell_char(), |(c, _)| c))
+                .collect();
+            writeln!(out, "{line}")?;
+        }
+        Ok(())
+    }
+}
+
+/// 24-bit ANSI colour escapes. Cells are coloured in the foreground, overlays in the background
+pub struct AnsiRenderer;
+
+impl Renderer for AnsiRenderer {
+    fn render<T: GridSquareDisplay>(&self, grid: &Grid<T>, overlays: &[PathOverlay], out: &mut dyn Write) -> Result<()> {
+        let marks = overlay_grid(grid, overlays);
+        for (r, m) in grid.rows().zip(marks.rows()) {
+            let mut line = String::new();
+            let mut current = None;
+            for (cell, mark) in r.iter().zip(m.iter()) {
+                let (c, bg) = match mark {
+                    Some((c, bg)) => (*c, Some(*bg)),
+                    None => (cell.cell_char(), None)
+                };
+                let style = (cell.cell_color(), bg);
+                if current != Some(style) {
+                    line.push_str("\x1b[0m");
+                    if let Some(fg) = style.0 {
+                        write!(line, "\x1b[38;2;{};{};{}m", fg.r, fg.g, fg.b)?;
+                    }
+                    if let Some(bg) = style.1 {
+                        write!(line, "\x1b[48;2;{};{};{}m", bg.r, bg.g, bg.b)?;
+                    }
+                    current = Some(style);
+                }
+                line.push(c);
+            }
+            writeln!(out, "{line}\x1b[0m")?;
+        }
+        Ok(())
+    }
+}
+
+/// A PNG image with each cell drawn as a `cell_size` square
+pub struct PngRenderer {
+    pub cell_size: u32
+}
+
+impl Renderer for PngRenderer {
+    fn render<T: GridSquareDisplay>(&self, grid: &Grid<T>, overlays: &[PathOverlay], out: &mut dyn Write) -> Result<()> {
+        let image = rasterize(grid, overlays, self.cell_size);
+        image::codecs::png::PngEncoder::new(out)
+            .encode(&image, image.width(), image.height(), image::ColorType::Rgb8)?;
+        Ok(())
+    }
+}
+
+/// Draw the grid into an RGB image with each cell `cell_size` pixels square. Overlays are drawn as
+/// a smaller square in the middle of each cell on the path
+pub fn rasterize<T: GridSquareDisplay>(grid: &Grid<T>, overlays: &[PathOverlay], cell_size: u32) -> image::RgbImage {
+    let cell_size = cell_size.max(1);
+    let marks = overlay_grid(grid, overlays);
+    let inset = cell_size / 4;
+    image::RgbImage::from_fn(grid.width() as u32 * cell_size, grid.height() as u32 * cell_size, |x, y| {
+        let coord = Coord { row: (y / cell_size) as usize, col: (x / cell_size) as usize };
+        let (cx, cy) = (x % cell_size, y % cell_size);
+        let in_mark = inset <= cx && cx < cell_size - inset && inset <= cy && cy < cell_size - inset;
+        let color = match marks[&coord] {
+            Some((_, color)) if in_mark => color,
+            _ => image_color(&grid[&coord])
+        };
+        image::Rgb([color.r, color.g, color.b])
+    })
+}
+
+/// An SVG document with one rectangle per run of same-coloured cells and a polyline per overlay
+pub struct SvgRenderer {
+    pub cell_size: u32
+}
+
+impl Renderer for SvgRenderer {
+    fn render<T: GridSquareDisplay>(&self, grid: &Grid<T>, overlays: &[PathOverlay], out: &mut dyn Write) -> Result<()> {
+        let cs = self.cell_size.max(1) as usize;
+        let (width, height) = (grid.width() * cs, grid.height() * cs);
+        writeln!(out, r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}" shape-rendering="crispEdges">"#)?;
+        for (row, r) in grid.rows().enumerate() {
+            let colors: Vec<Color> = r.iter().map(image_color).collect();
+            let mut start = 0;
+            while start < colors.len() {
+                let color = colors[start];
+                let len = colors[start..].iter().take_while(|c| **c == color).count();
+                writeln!(out, r#"<rect x="{}" y="{}" width="{}" height="{cs}" fill="{}"/>"#,
+                    start * cs, row * cs, len * cs, color.hex())?;
+                start += len;
+            }
+        }
+        for overlay in overlays {
+            let points: Vec<String> = overlay.coords.iter()
+                .map(|c| format!("{},{}", c.col * cs + cs / 2, c.row * cs + cs / 2))
+                .collect();
+            writeln!(out, r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="{}" stroke-linejoin="round"/>"#,
+                points.join(" "), overlay.color.hex(), (cs as f32 / 3.0).max(1.0))?;
+        }
+        writeln!(out, "</svg>")?;
+        Ok(())
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum RenderFormat {
+    Text,
+    Ansi,
+    Png,
+    Svg
+}
+
+#[derive(Args, Clone)]
+pub struct RenderCLI {
+    /// Render the grid in this format. Nothing is rendered unless this or `--render-to` is given
+    #[arg(long = "render", value_enum)]
+    format: Option<RenderFormat>,
+    /// Write the rendered grid to this file instead of stdout
+    #[arg(long)]
+    render_to: Option<PathBuf>,
+    /// Size in pixels of one grid cell in PNG and SVG output
+    #[arg(long, default_value_t = 4)]
+    cell_size: u32,
+}
+
+impl RenderCLI {
+    /// Render the grid if asked to on the command line. Refuses to write PNG data to a terminal
+    pub fn render<T: GridSquareDisplay>(&self, grid: &Grid<T>, overlays: &[PathOverlay]) -> Result<()> {
+        if self.format.is_none() && self.render_to.is_none() {
+            return Ok(());
+        }
+        self.write(grid, overlays)
+    }
+
+    /// Render the grid as asked on the command line, or as plain text on stdout if not asked
+    pub fn render_or_print<T: GridSquareDisplay>(&self, grid: &Grid<T>, overlays: &[PathOverlay]) -> Result<()> {
+        self.write(grid, overlays)
+    }
+
+    fn write<T: GridSquareDisplay>(&self, grid: &Grid<T>, overlays: &[PathOverlay]) -> Result<()> {
+        let format = self.format.unwrap_or(RenderFormat::Text);
+        if format == RenderFormat::Png && self.render_to.is_none() && io::stdout().is_terminal() {
+            bail!("Not writing PNG data to a terminal, use --render-to or redirect stdout");
+        }
+        let mut out: Box<dyn Write> = match &self.render_to {
+            Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+            None => Box::new(io::stdout().lock())
+        };
+        match format {
+            RenderFormat::Text => TextRenderer.render(grid, overlays, &mut out)?,
+            RenderFormat::Ansi => AnsiRenderer.render(grid, overlays, &mut out)?,
+            RenderFormat::Png => PngRenderer { cell_size: self.cell_size }.render(grid, overlays, &mut out)?,
+            RenderFormat::Svg => SvgRenderer { cell_size: self.cell_size }.render(grid, overlays, &mut out)?,
+        }
+        out.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone)]
+    struct Cell(char);
+
+    impl GridSquareDisplay for Cell {
+        fn cell_char(&self) -> char {
+            self.0
+        }
+
+        fn cell_color(&self) -> Option<Color> {
+            (self.0 == '#').then_some(Color::RED)
+        }
+    }
+
+    /// A diagonal of rock with a path along the top right
+    fn example() -> (Grid<Cell>, Vec<PathOverlay>) {
+        let mut grid = Grid::fill(3, 3, Cell('.'));
+        for i in 0..3 {
+            grid[&Coord { row: i, col: i }] = Cell('#');
+        }
+        let path = vec![Coord { row: 0, col: 1 }, Coord { row: 0, col: 2 }, Coord { row: 1, col: 2 }];
+        (grid, vec![PathOverlay::new(path, Color::GREEN)])
+    }
+
+    fn rendered(renderer: impl Renderer) -> String {
+        let (grid, overlays) = example();
+        let mut out = vec![];
+        renderer.render(&grid, &overlays, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn text() {
+        assert_eq!(rendered(TextRenderer), "#>v\n.#*\n..#\n");
+    }
+
+    #[test]
+    fn ansi() {
+        let (red, green, reset) = ("\x1b[38;2;220;40;40m", "\x1b[48;2;40;180;60m", "\x1b[0m");
+        let expected = [
+            format!("{reset}{red}#{reset}{green}>v{reset}"),
+            format!("{reset}.{reset}{red}#{reset}{green}*{reset}"),
+            format!("{reset}..{reset}{red}#{reset}"),
+        ].map(|line| line + "\n").concat();
+        assert_eq!(rendered(AnsiRenderer), expected);
+    }
+
+    #[test]
+    fn svg() {
+        let expected = r##"<svg xmlns="http://www.w3.org/2000/svg" width="6" height="6" viewBox="0 0 6 6" shape-rendering="crispEdges">
+<rect x="0" y="0" width="2" height="2" fill="#dc2828"/>
+<rect x="2" y="0" width="4" height="2" fill="#000000"/>
+<rect x="0" y="2" width="2" height="2" fill="#000000"/>
+<rect x="2" y="2" width="2" height="2" fill="#dc2828"/>
+<rect x="4" y="2" width="2" height="2" fill="#000000"/>
+<rect x="0" y="4" width="4" height="2" fill="#000000"/>
+<rect x="4" y="4" width="2" height="2" fill="#dc2828"/>
+<polyline points="3,1 5,1 5,3" fill="none" stroke="#28b43c" stroke-width="1" stroke-linejoin="round"/>
+</svg>
+"##;
+        assert_eq!(rendered(SvgRenderer { cell_size: 2 }), expected);
+    }
+
+    #[test]
+    fn png() {
+        let (grid, overlays) = example();
+        let mut out = vec![];
+        PngRenderer { cell_size: 4 }.render(&grid, &overlays, &mut out).unwrap();
+        let image = image::load_from_memory(&out).unwrap().into_rgb8();
+        assert_eq!(image.dimensions(), (12, 12));
+        // cell corners keep the cell colour and the middle of a path cell takes the overlay's
+        assert_eq!(image.get_pixel(0, 0).0, [220, 40, 40]);
+        assert_eq!(image.get_pixel(4, 0).0, [0, 0, 0]);
+        assert_eq!(image.get_pixel(5, 1).0, [40, 180, 60]);
+    }
+}
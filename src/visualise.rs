@@ -0,0 +1,308 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::time::Duration;
+
+use clap::Args;
+use color_eyre::eyre::{Result, eyre};
+use image::{Delay, Frame, RgbImage, RgbaImage};
+use image::codecs::gif::{GifEncoder, Repeat};
+use speedy2d::dimen::UVec2;
+use speedy2d::shape::Rectangle;
+use speedy2d::window::{WindowHandler, WindowHelper, WindowStartupInfo};
+
+use crate::render::Color;
+
+/// Something that advances in discrete steps and can draw itself as a grid of coloured cells
+pub trait Simulation {
+    fn step(&mut self);
+
+    /// Size of the drawn scene in cells, as (columns, rows)
+    fn dimensions(&self) -> (usize, usize);
+
+    /// Draw the current state. Row 0 is the top of the scene
+    fn draw(&self, canvas: &mut dyn Canvas);
+
+    /// Printed when the stop condition is reached
+    fn report(&self) -> String {
+        String::new()
+    }
+}
+
+pub trait Canvas {
+    fn fill_cell(&mut self, column: usize, row: usize, color: Color);
+}
+
+/// Decides when the visualiser stops stepping the simulation
+pub type StopCondition<S> = Box<dyn Fn(&S) -> bool>;
+
+#[derive(Args, Clone)]
+pub struct VisualiseCLI {
+    /// Show the simulation in a window. Space pauses, `.` steps while paused, `+` and `-` change
+    /// the speed and `q` quits
+    #[arg(long)]
+    pub gui: bool,
+    /// Write frames without a display: an animated GIF if the path ends in `.gif`, otherwise a
+    /// directory of numbered PNG files
+    #[arg(long)]
+    pub frames: Option<PathBuf>,
+    /// Simulation steps per second in the window
+    #[arg(long, default_value_t = 1000)]
+    pub tick_rate: u32,
+    /// Simulation steps between recorded frames
+    #[arg(long, default_value_t = 1)]
+    pub frame_every: usize,
+    /// Stop writing frames after this many, though the simulation still runs to the end
+    #[arg(long, default_value_t = 10_000)]
+    pub max_frames: usize,
+    /// Size in pixels of one scene cell
+    #[arg(long, default_value_t = 16)]
+    pub pixels_per_cell: u32,
+}
+
+impl VisualiseCLI {
+    pub fn enabled(&self) -> bool {
+        self.gui || self.frames.is_some()
+    }
+
+    /// Run the simulation in a window, or record it when `--frames` is given. Running in a
+    /// window only returns if the window can't be opened
+    pub fn run<S: Simulation + 'static>(&self, simulation: S, stop: StopCondition<S>) -> Result<S> {
+        if let Some(path) = &self.frames {
+            Recorder {
+                path: path.clone(),
+                frame_every: self.frame_every.max(1),
+                max_frames: self.max_frames,
+                cell_size: self.pixels_per_cell.max(1)
+            }.record(simulation, stop)
+        } else {
+            Visualiser::new(simulation, stop, self.tick_rate, self.pixels_per_cell).show()
+        }
+    }
+}
+
+/// Steps a simulation in a speedy2d window
+pub struct Visualiser<S> {
+    simulation: S,
+    stop: StopCondition<S>,
+    cell_size: u32,
+    tick_rate: Arc<AtomicU32>,
+    paused: Arc<AtomicBool>,
+    win_size: UVec2,
+}
+
+impl<S: Simulation + 'static> Visualiser<S> {
+    const MAX_TICK_RATE: u32 = 1_000_000;
+    /// Rates above this are reached by taking several steps per event
+    const MAX_EVENT_RATE: u32 = 1000;
+
+    pub fn new(simulation: S, stop: StopCondition<S>, tick_rate: u32, cell_size: u32) -> Self {
+        Self {
+            simulation,
+            stop,
+            cell_size: cell_size.max(1),
+            tick_rate: Arc::new(tick_rate.clamp(1, Self::MAX_TICK_RATE).into()),
+            paused: Arc::new(false.into()),
+            win_size: UVec2::ZERO,
+        }
+    }
+
+    /// Open the window and run until it is closed, which exits the process
+    pub fn show(self) -> Result<S> {
+        let (columns, rows) = self.simulation.dimensions();
+        let size = (columns as u32 * self.cell_size, rows as u32 * self.cell_size);
+        let window = speedy2d::Window::new_centered("Advent of Code", size)
+            .map_err(|e| eyre!("Couldn't open a window: {}", e))?;
+        window.run_loop(self)
+    }
+
+    fn steps_per_event(&self) -> u32 {
+        (self.tick_rate.load(Ordering::Relaxed) / Self::MAX_EVENT_RATE).max(1)
+    }
+
+    /// Take a step unless already stopped. Returns whether the stop condition has been reached
+    fn advance(&mut self) -> bool {
+        if (self.stop)(&self.simulation) {
+            return true;
+        }
+        self.simulation.step();
+        (self.stop)(&self.simulation)
+    }
+
+    fn finish(&self, helper: &mut WindowHelper<()>) {
+        println!("{}", self.simulation.report());
+        helper.terminate_loop()
+    }
+
+    fn set_tick_rate(&self, rate: u32) {
+        let rate = rate.clamp(1, Self::MAX_TICK_RATE);
+        self.tick_rate.store(rate, Ordering::Relaxed);
+        println!("{rate} steps per second");
+    }
+}
+
+impl<S: Simulation + 'static> WindowHandler for Visualiser<S> {
+    fn on_start(&mut self, helper: &mut WindowHelper<()>, info: WindowStartupInfo) {
+        self.win_size = *info.viewport_size_pixels();
+
+        let eventer = helper.create_user_event_sender();
+        let tick_rate = self.tick_rate.clone();
+        let paused = self.paused.clone();
+        std::thread::spawn(move || {
+            loop {
+                let events = tick_rate.load(Ordering::Relaxed).min(Self::MAX_EVENT_RATE);
+                std::thread::sleep(Duration::from_secs(1) / events);
+                if !paused.load(Ordering::Relaxed) && eventer.send_event(()).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+
+    fn on_resize(&mut self, helper: &mut WindowHelper<()>, size_pixels: UVec2) {
+        self.win_size = size_pixels;
+        helper.request_redraw();
+    }
+
+    fn on_user_event(&mut self, helper: &mut WindowHelper<()>, _user_event: ()) {
+        for _ in 0..self.steps_per_event() {
+            if self.advance() {
+                return self.finish(helper);
+            }
+        }
+        helper.request_redraw();
+    }
+
+    fn on_keyboard_char(&mut self, helper: &mut WindowHelper<()>, unicode_codepoint: char) {
+        match unicode_codepoint {
+            ' ' => { self.paused.fetch_xor(true, Ordering::Relaxed); },
+            '.' if self.paused.load(Ordering::Relaxed) => {
+                if self.advance() {
+                    return self.finish(helper);
+                }
+                helper.request_redraw();
+            },
+            '+' | '=' => self.set_tick_rate(self.tick_rate.load(Ordering::Relaxed).saturating_mul(2)),
+            '-' => self.set_tick_rate(self.tick_rate.load(Ordering::Relaxed) / 2),
+            'q' => helper.terminate_loop(),
+            _ => {}
+        }
+    }
+
+    fn on_draw(&mut self, _helper: &mut WindowHelper<()>, graphics: &mut speedy2d::Graphics2D) {
+        let (columns, rows) = self.simulation.dimensions();
+        let cell_size = (self.win_size.x / columns.max(1) as u32)
+            .min(self.win_size.y / rows.max(1) as u32)
+            .max(1) as f32;
+        let left = (self.win_size.x as f32 - cell_size * columns as f32) / 2.0;
+
+        graphics.clear_screen(speedy2d::color::Color::WHITE);
+        let mut canvas = WindowCanvas { graphics, left, cell_size };
+        self.simulation.draw(&mut canvas);
+    }
+}
+
+struct WindowCanvas<'a> {
+    graphics: &'a mut speedy2d::Graphics2D,
+    left: f32,
+    cell_size: f32,
+}
+
+impl Canvas for WindowCanvas<'_> {
+    fn fill_cell(&mut self, column: usize, row: usize, color: Color) {
+        let x = self.left + column as f32 * self.cell_size;
+        let y = row as f32 * self.cell_size;
+        self.graphics.draw_rectangle(
+            Rectangle::from_tuples((x + 1.0, y + 1.0), (x + self.cell_size - 1.0, y + self.cell_size - 1.0)),
+            speedy2d::color::Color::from_int_rgb(color.r, color.g, color.b));
+    }
+}
+
+struct ImageCanvas {
+    image: RgbImage,
+    cell_size: u32,
+}
+
+impl Canvas for ImageCanvas {
+    fn fill_cell(&mut self, column: usize, row: usize, color: Color) {
+        let (x0, y0) = (column as u32 * self.cell_size, row as u32 * self.cell_size);
+        for y in y0..(y0 + self.cell_size).min(self.image.height()) {
+            for x in x0..(x0 + self.cell_size).min(self.image.width()) {
+                self.image.put_pixel(x, y, image::Rgb([color.r, color.g, color.b]));
+            }
+        }
+    }
+}
+
+/// Renders frames of a simulation to image files without opening a window
+pub struct Recorder {
+    pub path: PathBuf,
+    pub frame_every: usize,
+    pub max_frames: usize,
+    pub cell_size: u32,
+}
+
+impl Recorder {
+    /// Delay between frames of an animated GIF
+    const GIF_FRAME_MS: u32 = 40;
+
+    pub fn frame<S: Simulation>(&self, simulation: &S) -> RgbImage {
+        let (columns, rows) = simulation.dimensions();
+        let image = RgbImage::from_pixel(columns as u32 * self.cell_size, rows as u32 * self.cell_size, image::Rgb([255, 255, 255]));
+        let mut canvas = ImageCanvas { image, cell_size: self.cell_size };
+        simulation.draw(&mut canvas);
+        canvas.image
+    }
+
+    /// Step the simulation until it stops, saving every `frame_every`th state until `max_frames`
+    /// have been written. Returns the simulation so its result can be reported
+    pub fn record<S: Simulation>(&self, mut simulation: S, stop: StopCondition<S>) -> Result<S> {
+        let is_gif = self.path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("gif"));
+        let mut gif = if is_gif {
+            let mut encoder = GifEncoder::new(BufWriter::new(File::create(&self.path)?));
+            encoder.set_repeat(Repeat::Infinite)?;
+            Some(encoder)
+        } else {
+            std::fs::create_dir_all(&self.path)?;
+            None
+        };
+
+        let mut frames = 0;
+        let mut steps = 0;
+        loop {
+            let stopped = stop(&simulation);
+            if frames == self.max_frames {
+                if !stopped {
+                    eprintln!("Stopped recording after {} frames, use --max-frames or --frame-every to see more", frames);
+                    while !stop(&simulation) {
+                        simulation.step();
+                    }
+                }
+                break;
+            }
+            if steps % self.frame_every == 0 || stopped {
+                let image = self.frame(&simulation);
+                match &mut gif {
+                    Some(encoder) => {
+                        let rgba = RgbaImage::from_fn(image.width(), image.height(), |x, y| {
+                            let [r, g, b] = image.get_pixel(x, y).0;
+                            image::Rgba([r, g, b, 255])
+                        });
+                        encoder.encode_frame(Frame::from_parts(rgba, 0, 0, Delay::from_numer_denom_ms(Self::GIF_FRAME_MS, 1)))?;
+                    },
+                    None => image.save(self.path.join(format!("frame_{frames:05}.png")))?
+                }
+                frames += 1;
+            }
+            if stopped {
+                break;
+            }
+            simulation.step();
+            steps += 1;
+        }
+        println!("{}", simulation.report());
+        Ok(simulation)
+    }
+}